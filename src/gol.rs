@@ -1,20 +1,40 @@
+pub mod board;
 pub mod cell;
 pub mod debug;
+pub mod elements;
 pub mod game_over;
 pub mod grid;
+pub mod hashlife;
+pub mod history;
 pub mod input;
 pub mod interaction;
+pub mod level;
+pub mod neighbourhood;
+pub mod packed;
 pub mod pattern;
 pub mod patterns_io;
 pub mod player;
+pub mod region;
+pub mod render;
+pub mod rng;
+pub mod rule;
 pub mod score;
+pub mod sparse;
+pub mod speed;
+pub mod stability;
 pub mod state;
+pub mod stats;
+pub mod terrain;
+pub mod territory;
+pub mod topology;
+pub mod tower;
 pub mod ui;
+pub mod win;
 
 use bevy::prelude::*;
 
 use crate::{
-    gol::{grid::setup_grid, player::populate_player_region},
+    gol::{grid::setup_grid, player::populate_player_region, rng::restart_rng},
     screens::Screen,
 };
 
@@ -28,10 +48,31 @@ pub(super) fn plugin(app: &mut App) {
         debug::plugin,
         score::plugin,
         game_over::plugin,
+        rng::plugin,
+        history::plugin,
+        speed::plugin,
+        render::plugin,
+        stats::plugin,
+        stability::plugin,
+        // Tuples of plugins can't be longer than 15.
+        (
+            elements::plugin,
+            level::plugin,
+            territory::plugin,
+            tower::plugin,
+        ),
     ))
-    .insert_resource(state::Playing(false))
+    .insert_resource(state::Playing(true))
     .add_systems(
         OnEnter(Screen::Gameplay),
-        (setup_grid, populate_player_region).chain(),
+        (restart_rng, setup_grid, populate_player_region).chain(),
     );
 }
+
+/// The entry `step` places after `current` in `all`, wrapping around, with
+/// -1 going backwards. A `current` not in `all` counts as the first entry.
+pub fn cycle<T: PartialEq + Copy>(all: &[T], current: T, step: i32) -> T {
+    let len = all.len() as i32;
+    let current = all.iter().position(|&t| t == current).unwrap_or(0) as i32;
+    all[(current + step).rem_euclid(len) as usize]
+}
@@ -0,0 +1,775 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::gol::{
+    cell::{CellState, CellType, Outcome, Owner, RegionOwner},
+    elements::Elements,
+    neighbourhood::Neighbourhood,
+    packed,
+    rng::CellRng,
+    rule::{BattleRule, Rules},
+    sparse::SparseWorld,
+    terrain::Terrain,
+    topology::Topology,
+};
+
+/// Dense, double-buffered Game of Life board.
+///
+/// The board owns the simulation state and knows nothing about entities,
+/// sprites or schedules, so the rules can be stepped from tests and tools
+/// as well as from the Bevy systems that mirror it on screen.
+#[derive(Resource, Clone)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    neighbourhood: Neighbourhood,
+    /// [`Neighbourhood::offsets`] for even and odd rows.
+    offsets: [Vec<IVec2>; 2],
+    cells: Vec<CellState>,
+    next: Vec<CellState>,
+    /// Who each cell belongs to, see [`Owner`].
+    owners: Vec<Owner>,
+    next_owners: Vec<Owner>,
+    /// The side each cell's region starts out belonging to.
+    regions: Vec<RegionOwner>,
+    terrain: Vec<Terrain>,
+    /// The world around the grid, with [`Topology::Unbounded`].
+    world: Option<SparseWorld>,
+}
+
+impl Board {
+    /// Creates a board with every cell dead and unowned.
+    pub fn new(width: usize, height: usize) -> Self {
+        let len = width * height;
+        Self {
+            width,
+            height,
+            topology: Topology::default(),
+            neighbourhood: Neighbourhood::default(),
+            offsets: [false, true].map(|odd| Neighbourhood::default().offsets(odd)),
+            cells: vec![CellState::Dead; len],
+            next: vec![CellState::Dead; len],
+            owners: vec![Owner::NONE; len],
+            next_owners: vec![Owner::NONE; len],
+            regions: vec![RegionOwner::None; len],
+            terrain: vec![Terrain::Open; len],
+            world: None,
+        }
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.set_topology(topology);
+        self
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        if topology != Topology::Unbounded {
+            self.world = None;
+        } else if self.world.is_none() {
+            self.world = Some(SparseWorld::default());
+        }
+    }
+
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.set_neighbourhood(neighbourhood);
+        self
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        self.neighbourhood = neighbourhood;
+        self.offsets = [false, true].map(|odd| neighbourhood.offsets(odd));
+    }
+
+    /// Offsets of the neighbours of a cell in row `y`.
+    pub fn offsets(&self, y: usize) -> &[IVec2] {
+        &self.offsets[y % 2]
+    }
+
+    /// The world around the grid, with [`Topology::Unbounded`].
+    pub fn world(&self) -> Option<&SparseWorld> {
+        self.world.as_ref()
+    }
+
+    pub fn world_mut(&mut self) -> Option<&mut SparseWorld> {
+        self.world.as_mut()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> CellState {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, state: CellState) {
+        let i = self.index(x, y);
+        self.cells[i] = state;
+    }
+
+    pub fn owner(&self, x: usize, y: usize) -> Owner {
+        self.owners[self.index(x, y)]
+    }
+
+    pub fn set_owner(&mut self, x: usize, y: usize, owner: Owner) {
+        let i = self.index(x, y);
+        self.owners[i] = owner;
+    }
+
+    pub fn region(&self, x: usize, y: usize) -> RegionOwner {
+        self.regions[self.index(x, y)]
+    }
+
+    pub fn set_region(&mut self, x: usize, y: usize, region: RegionOwner) {
+        let i = self.index(x, y);
+        self.regions[i] = region;
+    }
+
+    pub fn terrain(&self, x: usize, y: usize) -> Terrain {
+        self.terrain[self.index(x, y)]
+    }
+
+    /// Sets the terrain at `(x, y)`, clearing the cell if it can't live
+    /// there.
+    pub fn set_terrain(&mut self, x: usize, y: usize, terrain: Terrain) {
+        let i = self.index(x, y);
+        self.terrain[i] = terrain;
+        if !terrain.holds_cells() {
+            self.cells[i] = CellState::Dead;
+            self.owners[i] = Owner::NONE;
+        }
+    }
+
+    /// The terrain at a world position; everything beyond the grid is
+    /// open.
+    pub fn terrain_at(&self, pos: IVec2) -> Terrain {
+        if self.in_bounds(pos.x, pos.y) {
+            self.terrain(pos.x as usize, pos.y as usize)
+        } else {
+            Terrain::Open
+        }
+    }
+
+    /// Whether any cell has terrain other than [`Terrain::Open`].
+    pub fn has_terrain(&self) -> bool {
+        self.terrain.iter().any(|&terrain| terrain != Terrain::Open)
+    }
+
+    /// Terrain in the same order as [`Board::cells`].
+    pub fn terrains(&self) -> &[Terrain] {
+        &self.terrain
+    }
+
+    /// Unlike [`Board::set_terrain`] this leaves the cells alone.
+    pub fn terrains_mut(&mut self) -> &mut [Terrain] {
+        &mut self.terrain
+    }
+
+    /// Cell owners in the same order as [`Board::cells`].
+    pub fn owners(&self) -> &[Owner] {
+        &self.owners
+    }
+
+    pub fn owners_mut(&mut self) -> &mut [Owner] {
+        &mut self.owners
+    }
+
+    /// Cell states in row-major order, `y * width + x`.
+    pub fn cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [CellState] {
+        &mut self.cells
+    }
+
+    /// Iterates over `(x, y, state)` for every cell in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, CellState)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (i % self.width, i / self.width, *state))
+    }
+
+    /// Number of live cells of the given kind.
+    pub fn count(&self, kind: CellType) -> usize {
+        self.cells
+            .iter()
+            .filter(|state| state.is_alive() && state.kind() == kind)
+            .count()
+    }
+
+    /// Number of live cells owned by `owner`.
+    pub fn count_owned(&self, owner: Owner) -> usize {
+        self.cells
+            .iter()
+            .zip(&self.owners)
+            .filter(|(state, o)| state.is_alive() && **o == owner)
+            .count()
+    }
+
+    /// Number of live cells of the given kind owned by `owner`.
+    pub fn count_owned_kind(&self, owner: Owner, kind: CellType) -> usize {
+        self.cells
+            .iter()
+            .zip(&self.owners)
+            .filter(|(state, o)| state.is_alive() && state.kind() == kind && **o == owner)
+            .count()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(CellState::Dead);
+        self.owners.fill(Owner::NONE);
+        if let Some(world) = &mut self.world {
+            world.clear();
+        }
+    }
+
+    /// Maps a possibly off-board position to a board cell according to the
+    /// board's [`Topology`].
+    pub fn resolve(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        self.topology.resolve(x, y, self.width, self.height)
+    }
+
+    /// Kinds of the live cells in the [`Neighbourhood`] of `(x, y)`.
+    /// Positions beyond the edge are looked up through the [`Topology`].
+    pub fn alive_neighbors(&self, x: usize, y: usize) -> Vec<CellType> {
+        let offsets = self.offsets(y);
+        let mut alive_neighbors = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            let Some((nx, ny)) = self.resolve(x as i32 + offset.x, y as i32 + offset.y) else {
+                continue;
+            };
+            let state = self.get(nx, ny);
+            if state.is_alive() {
+                alive_neighbors.push(state.kind());
+            }
+        }
+        alive_neighbors
+    }
+
+    /// Advances the board by one generation under `rules`.
+    ///
+    /// The common [`BattleRule::TopTwo`] in the 3x3 Moore neighbourhood runs
+    /// on the bit-packed, multi-threaded kernel in
+    /// [`packed`](crate::gol::packed), which gives the same result as
+    /// [`Board::step_scalar`] for the same `rng`.
+    ///
+    /// An unbounded board always fights with [`BattleRule::TopTwo`], see
+    /// [`BattleRule::works_with`].
+    pub fn step(&mut self, rules: &Rules, rng: &mut impl Rng) {
+        let seed = rng.r#gen();
+        let neighbourhood = self.neighbourhood;
+        if self.world.is_some() {
+            if !rules.battle.works_with(self.topology) {
+                warn_once!(
+                    "{} doesn't work with {}; using Top two",
+                    rules.battle,
+                    self.topology
+                );
+            }
+            self.with_world(|world, board| {
+                world.step(rules, seed, neighbourhood, |pos| board.terrain_at(pos))
+            });
+            self.inherit_owners();
+            return;
+        }
+        match rules.battle {
+            BattleRule::TopTwo if neighbourhood == Neighbourhood::Moore(1) => {
+                packed::step_top_two(self, rules, seed)
+            }
+            BattleRule::TopTwo => self.step_top_two(rules, seed),
+            BattleRule::MajorityConversion => self.step_majority_conversion(rules, seed),
+        }
+        self.decay(rules);
+        self.inherit_owners();
+    }
+
+    /// Advances the board one cell at a time. This is the reference the
+    /// fast path of [`Board::step`] is checked against.
+    pub fn step_scalar(&mut self, rules: &Rules, rng: &mut impl Rng) {
+        let seed = rng.r#gen();
+        let neighbourhood = self.neighbourhood;
+        if self.world.is_some() {
+            self.with_world(|world, board| {
+                world.step(rules, seed, neighbourhood, |pos| board.terrain_at(pos))
+            });
+            self.inherit_owners();
+            return;
+        }
+        match rules.battle {
+            BattleRule::TopTwo => self.step_top_two(rules, seed),
+            BattleRule::MajorityConversion => self.step_majority_conversion(rules, seed),
+        }
+        self.decay(rules);
+        self.inherit_owners();
+    }
+
+    /// Applies [`Rules::decay`] to the generation just stepped to, with the
+    /// one before it still in `next`.
+    fn decay(&mut self, rules: &Rules) {
+        if !rules.decay || !rules.elements.has_decay() {
+            return;
+        }
+        for (state, &before) in self.cells.iter_mut().zip(&self.next) {
+            *state = rules.decay(before, *state);
+        }
+    }
+
+    /// Updates the owners for the generation just stepped to, with the one
+    /// before it still in `next`. Cells that were born or changed element
+    /// take the owner of most of their neighbours of that element, the
+    /// first seen on a tie; dead cells belong to nobody. Neighbours beyond
+    /// the grid of an unbounded board aren't known, so cells coming back
+    /// into view start out with nobody.
+    fn inherit_owners(&mut self) {
+        let mut owners = std::mem::take(&mut self.next_owners);
+        for (i, owner) in owners.iter_mut().enumerate() {
+            let before = self.next[i];
+            *owner = match self.cells[i] {
+                CellState::Alive(kind) if before.is_alive() && before.kind() == kind => {
+                    self.owners[i]
+                }
+                CellState::Alive(kind) => self.parent_owner(i, kind).unwrap_or(self.owners[i]),
+                CellState::Decaying(..) => self.owners[i],
+                _ => Owner::NONE,
+            };
+        }
+        self.next_owners = std::mem::replace(&mut self.owners, owners);
+    }
+
+    /// The owner of most of the live neighbours of kind `kind` that cell `i`
+    /// had in the generation before, kept in `next`.
+    fn parent_owner(&self, i: usize, kind: CellType) -> Option<Owner> {
+        let (x, y) = (i % self.width, i / self.width);
+        let mut counts: Vec<(Owner, usize)> = Vec::with_capacity(2);
+        for offset in &self.offsets[y % 2] {
+            let Some((nx, ny)) = self.resolve(x as i32 + offset.x, y as i32 + offset.y) else {
+                continue;
+            };
+            let j = self.index(nx, ny);
+            let parent = self.next[j];
+            if !parent.is_alive() || parent.kind() != kind {
+                continue;
+            }
+            let owner = self.owners[j];
+            match counts.iter_mut().find(|(o, _)| *o == owner) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((owner, 1)),
+            }
+        }
+        // `max_by_key` keeps the last of equal counts.
+        counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|&(owner, _)| owner)
+    }
+
+    fn step_top_two(&mut self, rules: &Rules, seed: u64) {
+        self.step_with(|board, next| {
+            for (i, next) in next.iter_mut().enumerate() {
+                let (x, y) = (i % board.width, i / board.width);
+                *next = board.next_top_two(x, y, rules, seed);
+            }
+        });
+    }
+
+    /// Advances an unbounded board `2^j` generations at once. Only works
+    /// in the 3x3 Moore neighbourhood on open ground while a single element
+    /// is alive; returns whether it did.
+    pub fn jump(&mut self, j: u8, rules: &Rules) -> bool {
+        if self.neighbourhood != Neighbourhood::Moore(1) || self.has_terrain() {
+            return false;
+        }
+        let jumped = self
+            .with_world(|world, _| world.jump(j, rules))
+            .unwrap_or(false);
+        if jumped {
+            self.inherit_owners();
+        }
+        jumped
+    }
+
+    /// Runs `f` on the world around an unbounded grid, with the grid's cells
+    /// written into the world before and read back after.
+    fn with_world<R>(&mut self, f: impl FnOnce(&mut SparseWorld, &Board) -> R) -> Option<R> {
+        let mut world = self.world.take()?;
+        let width = self.width;
+        let pos = |i: usize| IVec2::new((i % width) as i32, (i / width) as i32);
+        // Pick up edits made on the grid since the last step.
+        for (i, &state) in self.cells.iter().enumerate() {
+            world.set(pos(i), state);
+        }
+        let result = f(&mut world, self);
+        self.next.copy_from_slice(&self.cells);
+        for (i, state) in self.cells.iter_mut().enumerate() {
+            *state = world.get(pos(i));
+        }
+        self.world = Some(world);
+        Some(result)
+    }
+
+    /// Lets `f` fill in the next generation from the current one, then makes
+    /// it current.
+    pub(super) fn step_with(&mut self, f: impl FnOnce(&Board, &mut [CellState])) {
+        let mut next = std::mem::take(&mut self.next);
+        f(self, &mut next);
+        self.next = next;
+        std::mem::swap(&mut self.cells, &mut self.next);
+    }
+
+    /// The next state of the cell at `(x, y)` under [`BattleRule::TopTwo`],
+    /// with the cell's coin flips drawn from `seed`.
+    pub(super) fn next_top_two(&self, x: usize, y: usize, rules: &Rules, seed: u64) -> CellState {
+        let i = self.index(x, y);
+        let rng = &mut CellRng::new(seed, i);
+        let alive_neighbors = self.alive_neighbors(x, y);
+        next_top_two(self.cells[i], &alive_neighbors, self.terrain[i], rules, rng)
+    }
+
+    /// Port of the `brainstorm/index.html` prototype's `step()`.
+    fn step_majority_conversion(&mut self, rules: &Rules, seed: u64) {
+        // Phase 1: RPS conflict. Around every cell the winner of the two most
+        // frequent kinds converts the losing neighbours and the centre.
+        self.next.copy_from_slice(&self.cells);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let counts = kind_counts(&self.alive_neighbors(x, y));
+                let [(first, first_count), (second, second_count), ..] = counts[..] else {
+                    continue;
+                };
+                if first_count == second_count {
+                    continue;
+                }
+                // Phase 2 draws its coin flips from `seed` as well.
+                let rng = &mut CellRng::new(!seed, self.index(x, y));
+                let (winner, loser) = match rules.elements.battle(first, second, rng) {
+                    Outcome::Win => (first, second),
+                    Outcome::Lose => (second, first),
+                    Outcome::Draw => continue,
+                };
+                for offset in
+                    std::iter::once(IVec2::ZERO).chain(self.offsets[y % 2].iter().copied())
+                {
+                    let Some((nx, ny)) = self.resolve(x as i32 + offset.x, y as i32 + offset.y)
+                    else {
+                        continue;
+                    };
+                    let state = self.get(nx, ny);
+                    if state.is_alive() && state.kind() == loser {
+                        let i = self.index(nx, ny);
+                        self.next[i] = CellState::Alive(winner);
+                    }
+                }
+            }
+        }
+
+        // Phase 2: Life. Neighbours are read from before the conflict, births
+        // take the majority kind with a coin flip between tied leaders.
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = self.index(x, y);
+                let state = self.next[i];
+                let alive_neighbors = self.alive_neighbors(x, y);
+                let count = alive_neighbors.len();
+                if state.is_alive() {
+                    if !rules.for_kind(state.kind()).survives(count) {
+                        self.next[i] = CellState::Dead;
+                    }
+                } else if let Some(count) = self.terrain[i].birth_count(count)
+                    && rules.may_birth(count)
+                {
+                    let kind = match kind_counts(&alive_neighbors)[..] {
+                        [(first, first_count), (second, second_count), ..]
+                            if first_count == second_count =>
+                        {
+                            if CellRng::new(seed, i).gen_bool(0.5) {
+                                first
+                            } else {
+                                second
+                            }
+                        }
+                        [(first, _), ..] => first,
+                        [] => continue,
+                    };
+                    if rules.for_kind(kind).births(count) {
+                        self.next[i] = CellState::Alive(kind);
+                    }
+                }
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.next);
+    }
+
+    /// Runs `generations` steps in a row.
+    pub fn run(&mut self, generations: usize, rules: &Rules, rng: &mut impl Rng) {
+        for _ in 0..generations {
+            self.step(rules, rng);
+        }
+    }
+}
+
+/// The next state of a cell in `state` on `terrain` with `alive_neighbors`
+/// around it, under [`BattleRule::TopTwo`].
+pub fn next_top_two(
+    state: CellState,
+    alive_neighbors: &[CellType],
+    terrain: Terrain,
+    rules: &Rules,
+    rng: &mut impl Rng,
+) -> CellState {
+    let count = alive_neighbors.len();
+    if !terrain.holds_cells() {
+        CellState::Dead
+    } else if state.is_alive() {
+        if rules.for_kind(state.kind()).survives(count) {
+            alive_state_from(state.kind(), alive_neighbors, &rules.elements, rng)
+        } else {
+            CellState::Dead
+        }
+    } else if let Some(count) = terrain.birth_count(count)
+        && rules.may_birth(count)
+    {
+        let born = alive_state_from(
+            most_frequent_kind(alive_neighbors),
+            alive_neighbors,
+            &rules.elements,
+            rng,
+        );
+        // The element that wins the birth decides if it takes.
+        if rules.for_kind(born.kind()).births(count) {
+            born
+        } else {
+            CellState::Dead
+        }
+    } else {
+        CellState::Dead
+    }
+}
+
+fn most_frequent_kind(alive_neighbors: &[CellType]) -> CellType {
+    if alive_neighbors.is_empty() {
+        warn!("No alive neighbors found, returning default CellType::PLAIN_ON");
+        return CellType::PLAIN_ON;
+    }
+    // Ties go to the kind seen first so the result doesn't depend on hashing.
+    kind_counts(alive_neighbors)
+        .first()
+        .map_or(CellType::EMPTY, |&(kind, _)| kind)
+}
+
+/// How often each kind occurs, most frequent first. Kinds with the same
+/// count keep the order they were first seen in.
+fn kind_counts(alive_neighbors: &[CellType]) -> Vec<(CellType, usize)> {
+    let mut counts: Vec<(CellType, usize)> = Vec::with_capacity(3);
+    for &kind in alive_neighbors {
+        match counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+fn alive_state_from(
+    current_kind_or_most_alive: CellType,
+    alive_neighbors: &[CellType],
+    elements: &Elements,
+    rng: &mut impl Rng,
+) -> CellState {
+    if alive_neighbors.is_empty() {
+        return CellState::Alive(current_kind_or_most_alive);
+    }
+
+    //top two kinds by frequency in alive_neighbors plus current_kind_or_most_alive
+    let top_two_kinds: (CellType, CellType) = alive_neighbors.iter().fold(
+        (current_kind_or_most_alive, current_kind_or_most_alive),
+        |(a, b), &neighbor| {
+            if neighbor == a || neighbor == b {
+                (a, b)
+            } else if rng.gen_bool(0.5) {
+                (neighbor, a)
+            } else {
+                (a, neighbor)
+            }
+        },
+    );
+    let next_kind = match elements.battle(top_two_kinds.0, top_two_kinds.1, rng) {
+        Outcome::Win => top_two_kinds.0,
+        Outcome::Lose => top_two_kinds.1,
+        Outcome::Draw => {
+            if rng.gen_bool(0.5) {
+                top_two_kinds.0
+            } else {
+                top_two_kinds.1
+            }
+        }
+    };
+
+    CellState::Alive(next_kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    /// A board with a tree at each of `cells`.
+    fn board_with(width: usize, height: usize, cells: &[(usize, usize)]) -> Board {
+        let mut board = Board::new(width, height);
+        for &(x, y) in cells {
+            board.set(x, y, CellState::Alive(CellType::TREE));
+        }
+        board
+    }
+
+    /// The live cells of `board`, in row-major order.
+    fn live(board: &Board) -> Vec<(usize, usize)> {
+        board
+            .iter()
+            .filter(|(_, _, state)| state.is_alive())
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let horizontal = [(1, 2), (2, 2), (3, 2)];
+        let mut board = board_with(5, 5, &horizontal);
+        let rules = Rules::default();
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        board.step(&rules, rng);
+        assert_eq!(live(&board), [(2, 1), (2, 2), (2, 3)]);
+        board.step(&rules, rng);
+        assert_eq!(live(&board), horizontal);
+    }
+
+    #[test]
+    fn block_is_still() {
+        let block = [(1, 1), (2, 1), (1, 2), (2, 2)];
+        let mut board = board_with(4, 4, &block);
+        let rules = Rules::default();
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        for _ in 0..10 {
+            board.step(&rules, rng);
+            assert_eq!(live(&board), block);
+        }
+    }
+
+    #[test]
+    fn glider_moves_one_cell_diagonally_every_four_generations() {
+        // Row 5 is `###`, row 6 `..#` and row 7 `.#.`, heading down and right.
+        let glider = [(1, 5), (2, 5), (3, 5), (3, 6), (2, 7)];
+        let mut board = board_with(10, 10, &glider);
+        let rules = Rules::default();
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        board.run(4, &rules, rng);
+        let moved = glider.map(|(x, y)| (x + 1, y - 1));
+        assert_eq!(live(&board), moved);
+    }
+
+    #[test]
+    fn dead_cells_decay_through_their_stages() {
+        let mut board = board_with(5, 5, &[(2, 2)]);
+        let rules = Rules {
+            decay: true,
+            ..Rules::default()
+        };
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        let stages = rules.elements.decay_len(CellType::TREE);
+        assert!(stages > 0);
+        for age in 0..stages {
+            board.step(&rules, rng);
+            assert!(board.get(2, 2) == CellState::Decaying(CellType::TREE, age as u8));
+        }
+        board.step(&rules, rng);
+        assert!(board.get(2, 2) == CellState::Dead);
+    }
+
+    #[test]
+    fn decaying_cells_are_not_neighbours() {
+        // Two live neighbours and a decaying one are too few for a birth.
+        let mut board = board_with(5, 5, &[(1, 1), (3, 3)]);
+        board.set(1, 3, CellState::Decaying(CellType::TREE, 0));
+        let rules = Rules {
+            decay: true,
+            ..Rules::default()
+        };
+
+        board.step(&rules, &mut StdRng::seed_from_u64(0));
+        assert!(!board.get(2, 2).is_alive());
+    }
+
+    #[test]
+    fn decaying_cells_block_births() {
+        let parents = [(1, 1), (3, 1), (2, 3)];
+        let rules = Rules {
+            decay: true,
+            ..Rules::default()
+        };
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        let mut empty = board_with(5, 5, &parents);
+        empty.step(&rules, rng);
+        assert!(empty.get(2, 2).is_alive());
+
+        let mut blocked = board_with(5, 5, &parents);
+        blocked.set(2, 2, CellState::Decaying(CellType::TREE, 0));
+        blocked.step(&rules, rng);
+        assert!(blocked.get(2, 2) == CellState::Decaying(CellType::TREE, 1));
+    }
+
+    #[test]
+    fn clear_kills_and_disowns_every_cell() {
+        let mut board = board_with(4, 4, &[(1, 1), (2, 2)]);
+        board.set_owner(1, 1, Owner::PLAYER);
+        board.set_owner(2, 2, Owner::AI);
+
+        board.clear();
+        assert!(board.cells().iter().all(CellState::is_dead));
+        assert!(board.owners().iter().all(|&owner| owner == Owner::NONE));
+    }
+
+    #[test]
+    fn terrain_without_cells_clears_the_cell_under_it() {
+        let mut board = board_with(4, 4, &[(1, 1), (2, 2)]);
+        board.set_owner(1, 1, Owner::PLAYER);
+        board.set_owner(2, 2, Owner::PLAYER);
+
+        board.set_terrain(1, 1, Terrain::Wall);
+        board.set_terrain(2, 2, Terrain::Open);
+        assert!(board.get(1, 1).is_dead());
+        assert_eq!(board.owner(1, 1), Owner::NONE);
+        assert!(!board.get(2, 2).is_dead());
+        assert_eq!(board.owner(2, 2), Owner::PLAYER);
+    }
+}
@@ -1,10 +1,13 @@
-use bevy::color::palettes::basic::*;
 use bevy::prelude::*;
-use rand::prelude::*;
 
-#[derive(Component, Clone, Copy, PartialEq)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CellState {
     Alive(CellType),
+    /// A dead cell of an element that still takes up its place, this many
+    /// generations after it died. See [`Element::decay`].
+    ///
+    /// [`Element::decay`]: crate::gol::elements::Element::decay
+    Decaying(CellType, u8),
     AlivePlain,
     DeadPlain,
     Dead,
@@ -16,27 +19,23 @@ impl CellState {
     pub fn is_dead(&self) -> bool {
         matches!(self, CellState::Dead)
     }
-    pub fn kind(&self) -> CellType {
-        match self {
-            CellState::Alive(kind) => *kind,
-            CellState::AlivePlain => CellType::PlainOn,
-            CellState::DeadPlain => CellType::PlainOff,
-            CellState::Dead => CellType::Empty,
-        }
+    pub fn is_decaying(&self) -> bool {
+        matches!(self, CellState::Decaying(..))
     }
-    pub fn color(&self) -> Color {
+    pub fn kind(&self) -> CellType {
         match self {
-            CellState::Alive(kind) => kind.color(),
-            CellState::AlivePlain => CellType::PlainOn.color(),
-            CellState::DeadPlain => CellType::PlainOff.color(),
-            CellState::Dead => CellType::Empty.color(),
+            CellState::Alive(kind) | CellState::Decaying(kind, _) => *kind,
+            CellState::AlivePlain => CellType::PLAIN_ON,
+            CellState::DeadPlain => CellType::PLAIN_OFF,
+            CellState::Dead => CellType::EMPTY,
         }
     }
     pub fn default_alive() -> Self {
-        CellState::Alive(CellType::Tree)
+        CellState::Alive(CellType::TREE)
     }
 }
 
+/// The side a region of the board starts out belonging to.
 #[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum RegionOwner {
     None,
@@ -44,53 +43,60 @@ pub enum RegionOwner {
     AI,
 }
 
-#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Hash)]
-pub enum CellType {
-    Tree,
-    Water,
-    Fire,
-    PlainOn,
-    PlainOff,
-    Empty,
-}
-pub enum Outcome {
-    Win,
-    Lose,
-    Draw,
-}
+/// Who a live cell belongs to. Cells are owned by whoever placed them, and
+/// cells born or converted take the owner of most of the neighbours that
+/// made them.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Owner(pub u8);
 
-impl CellType {
-    pub fn battle(&self, other: &Self) -> Outcome {
-        match (self, other) {
-            (CellType::Tree, CellType::Water)
-            | (CellType::Water, CellType::Fire)
-            | (CellType::Fire, CellType::Tree) => Outcome::Win,
-            (CellType::Water, CellType::Tree)
-            | (CellType::Fire, CellType::Water)
-            | (CellType::Tree, CellType::Fire) => Outcome::Lose,
-            _ => Outcome::Draw, // If they are the same or not in the cycle, return self
-        }
+impl Owner {
+    /// Cells nobody placed, and dead cells.
+    pub const NONE: Owner = Owner(0);
+    pub const PLAYER: Owner = Owner(1);
+    pub const AI: Owner = Owner(2);
+    /// Most owners a match can have, counting [`Owner::NONE`].
+    pub const MAX: usize = 8;
+
+    pub fn index(self) -> usize {
+        self.0 as usize
     }
-    pub fn colors(&self) -> Vec<Color> {
-        match self {
-            CellType::Tree => vec![GREEN.into(), OLIVE.into()],
-            CellType::Water => vec![BLUE.into(), AQUA.into()],
-            CellType::Fire => vec![RED.into(), FUCHSIA.into()],
-            CellType::Empty => vec![Color::srgb(0.1, 0.1, 0.1)],
-            CellType::PlainOn => vec![BLACK.into()],
-            CellType::PlainOff => vec![WHITE.into()],
+}
+
+impl From<RegionOwner> for Owner {
+    fn from(region: RegionOwner) -> Self {
+        match region {
+            RegionOwner::None => Owner::NONE,
+            RegionOwner::Player => Owner::PLAYER,
+            RegionOwner::AI => Owner::AI,
         }
     }
-    pub fn color(&self) -> Color {
-        let mut rng = rand::thread_rng();
-        *self.colors().choose(&mut rng).unwrap()
+}
+
+/// An element, by its place in the match's
+/// [`Elements`](crate::gol::elements::Elements), or one of the kinds of cells
+/// that aren't elements.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct CellType(pub u8);
+
+impl CellType {
+    /// The element the player protects.
+    pub const TREE: CellType = CellType(0);
+    /// The element the player places.
+    pub const WATER: CellType = CellType(1);
+    /// The element of the AI.
+    pub const FIRE: CellType = CellType(2);
+    pub const PLAIN_ON: CellType = CellType(u8::MAX - 2);
+    pub const PLAIN_OFF: CellType = CellType(u8::MAX - 1);
+    /// The kind of dead cells.
+    pub const EMPTY: CellType = CellType(u8::MAX);
+
+    pub fn index(self) -> usize {
+        self.0 as usize
     }
 }
 
-#[derive(Component)]
-pub struct Cell {
-    pub x: usize,
-    pub y: usize,
-    pub state: CellState,
-    pub region: RegionOwner,
+pub enum Outcome {
+    Win,
+    Lose,
+    Draw,
 }
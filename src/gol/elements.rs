@@ -0,0 +1,666 @@
+//! The elements cells can be, loaded from `.elements.json` assets.
+//!
+//! A file lists each element with its colours, the elements it beats and,
+//! optionally, its own birth/survival rule:
+//!
+//! ```json
+//! {
+//!   "name": "Classic",
+//!   "elements": [
+//!     { "name": "tree", "colors": ["#008000", "#808000"], "beats": ["water"], "rule": "B3/S12345" },
+//!     { "name": "water", "colors": ["#0000ff", "#00ffff"], "beats": ["fire"] },
+//!     {
+//!       "name": "fire", "colors": ["#ff0000", "#ff00ff"], "beats": [{ "name": "tree", "chance": 0.8 }],
+//!       "decay": [{ "name": "embers", "color": "#ff8000", "generations": 2 }, { "name": "ash", "color": "#505050" }]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! An element with a `chance` below one only wins that often; otherwise the
+//! battle is a draw. The first three elements play the parts of the trees
+//! the player protects, the water the player places and the fire of the AI.
+//! Others only get on the board through `"scatter"`, the share of the
+//! unowned cells they are sown on when a match starts.
+//!
+//! With decay turned on, a dead cell goes through its element's `"decay"`
+//! stages, each lasting `"generations"` (one if left out), before the
+//! place is free again.
+
+use std::{fmt, io};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    color::palettes::basic::*,
+    prelude::*,
+};
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    asset_tracking::LoadResource,
+    gol::{
+        self,
+        cell::{CellState, CellType, Outcome},
+        rule::{Rule, RuleParseError},
+    },
+};
+
+/// Most elements a table can have, so per-element tables can be arrays.
+pub const MAX_ELEMENTS: usize = 16;
+
+/// The element sets offered by the settings menu, under `assets/elements`.
+const ELEMENT_SETS: &[&str] = &["classic", "rpsls", "stone"];
+
+/// The elements of a match and who beats whom.
+#[derive(Asset, TypePath, Clone, Debug, PartialEq)]
+pub struct Elements {
+    pub name: String,
+    elements: Vec<Element>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub colors: Vec<Color>,
+    /// Used instead of the shared rule when per-element rules are on.
+    pub rule: Option<Rule>,
+    /// Share of the unowned cells this element starts on.
+    pub scatter: f32,
+    /// What a dead cell leaves behind, in order. Empty for elements that
+    /// are gone at once.
+    pub decay: Vec<DecayStage>,
+    /// Chance of beating each element, by index.
+    beats: Vec<f32>,
+}
+
+/// One stage a dead cell of an element goes through before it is gone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecayStage {
+    pub name: String,
+    pub color: Color,
+    pub generations: u8,
+}
+
+impl Default for Elements {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Elements {
+    /// Trees beat water beats fire beats trees. Trees are hard to kill but
+    /// slow to grow and leave stumps, fire spreads fast but burns out into
+    /// embers and ash, water plays by Conway's rules. Matches
+    /// `assets/elements/classic.elements.json`.
+    pub fn classic() -> Self {
+        let element =
+            |name: &str, colors: [Srgba; 2], beats: usize, rule, decay: &[(&str, Color, u8)]| {
+                Element {
+                    name: name.to_string(),
+                    colors: colors.map(Color::from).to_vec(),
+                    rule: Some(rule),
+                    scatter: 0.0,
+                    decay: decay
+                        .iter()
+                        .map(|&(name, color, generations)| DecayStage {
+                            name: name.to_string(),
+                            color,
+                            generations,
+                        })
+                        .collect(),
+                    beats: (0..3).map(|i| if i == beats { 1.0 } else { 0.0 }).collect(),
+                }
+            };
+        Self {
+            name: "Classic".to_string(),
+            elements: vec![
+                element(
+                    "tree",
+                    [GREEN, OLIVE],
+                    1,
+                    Rule::from_counts(&[3], &[1, 2, 3, 4, 5]),
+                    &[("stump", Color::srgb_u8(0x5c, 0x40, 0x33), 4)],
+                ),
+                element("water", [BLUE, AQUA], 2, Rule::CONWAY, &[]),
+                element(
+                    "fire",
+                    [RED, FUCHSIA],
+                    0,
+                    Rule::from_counts(&[3, 4], &[2]),
+                    &[
+                        ("embers", Color::srgb_u8(0xff, 0x80, 0x00), 2),
+                        ("ash", Color::srgb_u8(0x50, 0x50, 0x50), 3),
+                    ],
+                ),
+            ],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn get(&self, kind: CellType) -> Option<&Element> {
+        self.elements.get(kind.index())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Element> {
+        self.elements.iter()
+    }
+
+    /// Every element with its kind.
+    pub fn kinds(&self) -> impl Iterator<Item = (CellType, &Element)> {
+        self.elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| (CellType(i as u8), element))
+    }
+
+    /// The shades `kind` is drawn in. Kinds that aren't elements, like the
+    /// kind of dead cells, have fixed colours.
+    pub fn colors(&self, kind: CellType) -> Vec<Color> {
+        match kind {
+            CellType::EMPTY => vec![Color::srgb(0.1, 0.1, 0.1)],
+            CellType::PLAIN_ON => vec![BLACK.into()],
+            CellType::PLAIN_OFF => vec![WHITE.into()],
+            _ => self
+                .get(kind)
+                .map_or_else(|| vec![GRAY.into()], |element| element.colors.clone()),
+        }
+    }
+
+    /// The main colour of `kind`.
+    pub fn color(&self, kind: CellType) -> Color {
+        self.colors(kind)[0]
+    }
+
+    /// The own rule of `kind`, if it has one.
+    pub fn rule(&self, kind: CellType) -> Option<Rule> {
+        self.get(kind).and_then(|element| element.rule)
+    }
+
+    /// Whether any element leaves something behind when it dies.
+    pub fn has_decay(&self) -> bool {
+        self.elements
+            .iter()
+            .any(|element| !element.decay.is_empty())
+    }
+
+    /// Generations a dead cell of `kind` takes to decay.
+    pub fn decay_len(&self, kind: CellType) -> u32 {
+        self.get(kind).map_or(0, |element| {
+            element
+                .decay
+                .iter()
+                .map(|stage| stage.generations as u32)
+                .sum()
+        })
+    }
+
+    /// The stage a cell of `kind` is in `age` generations after it died.
+    pub fn decay_stage(&self, kind: CellType, age: u8) -> Option<&DecayStage> {
+        let mut left = age as u32;
+        self.get(kind)?.decay.iter().find(|stage| {
+            let current = left < stage.generations as u32;
+            left = left.saturating_sub(stage.generations as u32);
+            current
+        })
+    }
+
+    /// The colour of a cell of `kind` `age` generations after it died,
+    /// fading from each stage's colour into the next one's and lastly into
+    /// the colour of dead cells.
+    pub fn decay_color(&self, kind: CellType, age: u8) -> Color {
+        let dead = self.color(CellType::EMPTY);
+        let Some(element) = self.get(kind) else {
+            return dead;
+        };
+        let mut start = 0;
+        for (i, stage) in element.decay.iter().enumerate() {
+            let end = start + stage.generations as u32;
+            if (age as u32) < end {
+                let next = element.decay.get(i + 1).map_or(dead, |next| next.color);
+                let t = (age as u32 - start) as f32 / stage.generations as f32;
+                return stage.color.mix(&next, t);
+            }
+            start = end;
+        }
+        dead
+    }
+
+    /// The state of a cell that a step took from `before` to `after`, once
+    /// decay is applied: cells that died start decaying, and decaying cells
+    /// keep their place, whatever the step made of it, until they are gone.
+    pub fn decay(&self, before: CellState, after: CellState) -> CellState {
+        match before {
+            CellState::Decaying(kind, age) => {
+                let age = age.saturating_add(1);
+                if (age as u32) < self.decay_len(kind) {
+                    CellState::Decaying(kind, age)
+                } else {
+                    CellState::Dead
+                }
+            }
+            CellState::Alive(kind) if after.is_dead() && self.decay_len(kind) > 0 => {
+                CellState::Decaying(kind, 0)
+            }
+            _ => after,
+        }
+    }
+
+    /// Whether `kind` beats `other` some of the time.
+    pub fn beats(&self, kind: CellType, other: CellType) -> bool {
+        self.chance(kind, other) > 0.0
+    }
+
+    fn chance(&self, kind: CellType, other: CellType) -> f32 {
+        self.get(kind)
+            .and_then(|element| element.beats.get(other.index()))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The battle of `kind` against `other`. Only elements that win with a
+    /// chance below one draw from `rng`.
+    pub fn battle(&self, kind: CellType, other: CellType, rng: &mut impl Rng) -> Outcome {
+        let mut won = |chance: f32| chance >= 1.0 || (chance > 0.0 && rng.gen_bool(chance as f64));
+        if kind == other {
+            Outcome::Draw
+        } else if self.beats(kind, other) && won(self.chance(kind, other)) {
+            Outcome::Win
+        } else if self.beats(other, kind) && won(self.chance(other, kind)) {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// Reads an `.elements.json` file.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, ElementsError> {
+        let file: ElementsFile = serde_json::from_slice(bytes)?;
+        if !(3..=MAX_ELEMENTS).contains(&file.elements.len()) {
+            return Err(ElementsError::Count(file.elements.len()));
+        }
+        let index = |name: &str| {
+            file.elements
+                .iter()
+                .position(|element| element.name == name)
+                .ok_or_else(|| ElementsError::UnknownElement(name.to_string()))
+        };
+        let mut elements = Vec::with_capacity(file.elements.len());
+        for element in &file.elements {
+            let mut beats = vec![0.0; file.elements.len()];
+            for beat in &element.beats {
+                let (name, chance) = match beat {
+                    BeatFile::Always(name) => (name, 1.0),
+                    BeatFile::Sometimes { name, chance } => (name, chance.clamp(0.0, 1.0)),
+                };
+                beats[index(name)?] = chance;
+            }
+            let colors = element
+                .colors
+                .iter()
+                .map(|hex| Srgba::hex(hex).map(Color::from))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| ElementsError::Color(element.name.clone()))?;
+            if colors.is_empty() {
+                return Err(ElementsError::Color(element.name.clone()));
+            }
+            let decay = element
+                .decay
+                .iter()
+                .map(|stage| {
+                    let color = Srgba::hex(&stage.color)
+                        .map_err(|_| ElementsError::Color(element.name.clone()))?;
+                    Ok(DecayStage {
+                        name: stage.name.clone(),
+                        color: color.into(),
+                        generations: stage.generations,
+                    })
+                })
+                .collect::<Result<Vec<_>, ElementsError>>()?;
+            let decay_len: u32 = decay.iter().map(|stage| stage.generations as u32).sum();
+            if decay.iter().any(|stage| stage.generations == 0) || decay_len > u8::MAX as u32 {
+                return Err(ElementsError::Decay(element.name.clone()));
+            }
+            let rule = element
+                .rule
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|err| ElementsError::Rule(element.name.clone(), err))?;
+            elements.push(Element {
+                name: element.name.clone(),
+                colors,
+                rule,
+                scatter: element.scatter.clamp(0.0, 1.0),
+                decay,
+                beats,
+            });
+        }
+        Ok(Self {
+            name: file.name,
+            elements,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ElementsFile {
+    name: String,
+    elements: Vec<ElementFile>,
+}
+
+#[derive(Deserialize)]
+struct ElementFile {
+    name: String,
+    colors: Vec<String>,
+    #[serde(default)]
+    beats: Vec<BeatFile>,
+    #[serde(default)]
+    rule: Option<String>,
+    #[serde(default)]
+    scatter: f32,
+    #[serde(default)]
+    decay: Vec<DecayStageFile>,
+}
+
+#[derive(Deserialize)]
+struct DecayStageFile {
+    name: String,
+    color: String,
+    #[serde(default = "one_generation")]
+    generations: u8,
+}
+
+fn one_generation() -> u8 {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BeatFile {
+    Always(String),
+    Sometimes { name: String, chance: f32 },
+}
+
+#[derive(Debug)]
+pub enum ElementsError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// There must be at least three elements for the game's parts, and at
+    /// most [`MAX_ELEMENTS`].
+    Count(usize),
+    /// A `beats` entry names an element that isn't in the file.
+    UnknownElement(String),
+    /// The element has no colours or one that isn't a hex colour.
+    Color(String),
+    /// A decay stage of the element lasts no generations, or all of them
+    /// together last more than 255.
+    Decay(String),
+    Rule(String, RuleParseError),
+}
+
+impl fmt::Display for ElementsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementsError::Io(err) => write!(f, "could not read elements: {err}"),
+            ElementsError::Json(err) => write!(f, "invalid elements file: {err}"),
+            ElementsError::Count(n) => {
+                write!(f, "expected 3 to {MAX_ELEMENTS} elements, found {n}")
+            }
+            ElementsError::UnknownElement(name) => write!(f, "unknown element '{name}'"),
+            ElementsError::Color(name) => {
+                write!(f, "element '{name}' needs hex colours like #ff8800")
+            }
+            ElementsError::Decay(name) => {
+                write!(
+                    f,
+                    "decay of element '{name}' must last 1 to 255 generations"
+                )
+            }
+            ElementsError::Rule(name, err) => write!(f, "rule of element '{name}': {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ElementsError {}
+
+impl From<io::Error> for ElementsError {
+    fn from(err: io::Error) -> Self {
+        ElementsError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ElementsError {
+    fn from(err: serde_json::Error) -> Self {
+        ElementsError::Json(err)
+    }
+}
+
+#[derive(Default)]
+struct ElementsLoader;
+
+impl AssetLoader for ElementsLoader {
+    type Asset = Elements;
+    type Settings = ();
+    type Error = ElementsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Elements, ElementsError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Elements::from_json(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["elements.json"]
+    }
+}
+
+/// Every element set of [`ELEMENT_SETS`].
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct ElementAssets {
+    #[dependency]
+    sets: Vec<Handle<Elements>>,
+}
+
+impl FromWorld for ElementAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            sets: ELEMENT_SETS
+                .iter()
+                .map(|name| assets.load(format!("elements/{name}.elements.json")))
+                .collect(),
+        }
+    }
+}
+
+impl ElementAssets {
+    /// The element set after the one named `current`, wrapping around.
+    pub fn cycle(&self, current: &str, step: i32, assets: &Assets<Elements>) -> Option<Elements> {
+        let sets: Vec<&Elements> = self.sets.iter().filter_map(|h| assets.get(h)).collect();
+        let names: Vec<&str> = sets.iter().map(|set| set.name.as_str()).collect();
+        if names.is_empty() {
+            return None;
+        }
+        let next = gol::cycle(&names, current, step);
+        sets.into_iter().find(|set| set.name == next).cloned()
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Elements>()
+        .init_asset_loader::<ElementsLoader>()
+        .register_type::<ElementAssets>()
+        .load_resource::<ElementAssets>();
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn asset(name: &str) -> &'static [u8] {
+        match name {
+            "classic" => include_bytes!("../../assets/elements/classic.elements.json"),
+            "rpsls" => include_bytes!("../../assets/elements/rpsls.elements.json"),
+            "stone" => include_bytes!("../../assets/elements/stone.elements.json"),
+            _ => panic!("no element set {name}"),
+        }
+    }
+
+    #[test]
+    fn parses_the_bundled_element_sets() {
+        assert_eq!(
+            Elements::from_json(asset("classic")).unwrap(),
+            Elements::classic()
+        );
+        for name in ELEMENT_SETS {
+            let elements = Elements::from_json(asset(name)).unwrap();
+            assert!(elements.len() >= 3, "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_chances_decay_and_rules() {
+        let json = br##"{
+            "name": "Test",
+            "elements": [
+                { "name": "a", "colors": ["#000000"], "beats": ["b"], "rule": "B36/S23" },
+                { "name": "b", "colors": ["#ffffff"], "beats": [{ "name": "c", "chance": 0.5 }] },
+                {
+                    "name": "c", "colors": ["#ff0000"], "beats": ["a"],
+                    "decay": [{ "name": "x", "color": "#808080", "generations": 2 }, { "name": "y", "color": "#404040" }]
+                }
+            ]
+        }"##;
+        let elements = Elements::from_json(json).unwrap();
+        let [a, b, c] = [0, 1, 2].map(CellType);
+        assert_eq!(elements.name, "Test");
+        assert_eq!(elements.rule(a), Some("B36/S23".parse().unwrap()));
+        assert_eq!(elements.rule(b), None);
+        assert!(elements.beats(a, b) && elements.beats(b, c) && elements.beats(c, a));
+        assert!(!elements.beats(b, a));
+        assert_eq!(elements.decay_len(c), 3);
+        assert_eq!(elements.decay_len(a), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_element_sets() {
+        let file = |elements: &str| format!(r#"{{ "name": "Bad", "elements": [{elements}] }}"#);
+        let ok =
+            r##"{ "name": "a", "colors": ["#000000"] }, { "name": "b", "colors": ["#000000"] }"##;
+        let cases = [
+            (file(ok), "too few"),
+            (
+                file(&format!(
+                    r##"{ok}, {{ "name": "c", "colors": ["#000000"], "beats": ["z"] }}"##
+                )),
+                "unknown",
+            ),
+            (
+                file(&format!(r##"{ok}, {{ "name": "c", "colors": ["red!"] }}"##)),
+                "colour",
+            ),
+            (
+                file(&format!(r##"{ok}, {{ "name": "c", "colors": [] }}"##)),
+                "no colour",
+            ),
+            (
+                file(&format!(
+                    r##"{ok}, {{ "name": "c", "colors": ["#000000"], "decay": [{{ "name": "x", "color": "#000000", "generations": 0 }}] }}"##
+                )),
+                "decay",
+            ),
+            (
+                file(&format!(
+                    r##"{ok}, {{ "name": "c", "colors": ["#000000"], "rule": "B9/S2" }}"##
+                )),
+                "rule",
+            ),
+            ("not json".to_string(), "json"),
+        ];
+        for (json, case) in cases {
+            let err = Elements::from_json(json.as_bytes()).unwrap_err();
+            let expected = match case {
+                "too few" => matches!(err, ElementsError::Count(2)),
+                "unknown" => matches!(err, ElementsError::UnknownElement(ref name) if name == "z"),
+                "colour" | "no colour" => {
+                    matches!(err, ElementsError::Color(ref name) if name == "c")
+                }
+                "decay" => matches!(err, ElementsError::Decay(_)),
+                "rule" => matches!(err, ElementsError::Rule(..)),
+                _ => matches!(err, ElementsError::Json(_)),
+            };
+            assert!(expected, "{case}: {err}");
+        }
+    }
+
+    #[test]
+    fn battles_follow_who_beats_whom() {
+        let elements = Elements::classic();
+        let mut rng = StdRng::seed_from_u64(1);
+        let (tree, water, fire) = (CellType::TREE, CellType::WATER, CellType::FIRE);
+        assert!(matches!(
+            elements.battle(tree, water, &mut rng),
+            Outcome::Win
+        ));
+        assert!(matches!(
+            elements.battle(water, tree, &mut rng),
+            Outcome::Lose
+        ));
+        assert!(matches!(
+            elements.battle(water, fire, &mut rng),
+            Outcome::Win
+        ));
+        assert!(matches!(
+            elements.battle(fire, tree, &mut rng),
+            Outcome::Win
+        ));
+        assert!(matches!(
+            elements.battle(fire, fire, &mut rng),
+            Outcome::Draw
+        ));
+    }
+
+    #[test]
+    fn battles_won_by_chance_are_sometimes_draws() {
+        let json = br##"{
+            "name": "Chance",
+            "elements": [
+                { "name": "a", "colors": ["#000000"], "beats": [{ "name": "b", "chance": 0.5 }] },
+                { "name": "b", "colors": ["#000000"] },
+                { "name": "c", "colors": ["#000000"] }
+            ]
+        }"##;
+        let elements = Elements::from_json(json).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let wins = (0..1000)
+            .filter(|_| {
+                matches!(
+                    elements.battle(CellType(0), CellType(1), &mut rng),
+                    Outcome::Win
+                )
+            })
+            .count();
+        assert!((400..600).contains(&wins), "{wins}");
+        // Elements that don't beat each other always draw.
+        assert!(matches!(
+            elements.battle(CellType(1), CellType(2), &mut rng),
+            Outcome::Draw
+        ));
+    }
+}
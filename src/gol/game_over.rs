@@ -3,7 +3,18 @@ use bevy::prelude::*;
 
 use crate::{
     AppSystems, PausableSystems,
-    gol::score::{AiScore, PlayerScore},
+    gol::{
+        board::Board,
+        cell::Owner,
+        grid::{GridConfig, game_of_life_step},
+        history::History,
+        rng::GameRng,
+        score::{AiScore, Kills, PlayerScore},
+        speed::GenerationsStepped,
+        stability::Stability,
+        territory::Territories,
+        win::{Fielded, GameMode, MatchView, WinCondition, judge},
+    },
     menus::Menu,
     screens::Screen,
     theme::widget::{self, ui_root_left},
@@ -16,14 +27,47 @@ pub struct BestScore(pub i32);
 pub struct GameOverState {
     pub is_over: bool,
     pub message: String,
+    /// Which condition ended the match, and how.
+    pub reason: String,
+}
+
+/// The conditions the current match ends on, from its level or the
+/// [`GameMode`].
+#[derive(Resource, Default)]
+pub struct WinConditions {
+    pub conditions: Vec<WinCondition>,
 }
 
 #[derive(Component)]
 struct GameOverUi;
 
+fn start_match(
+    config: Res<GridConfig>,
+    mode: Res<GameMode>,
+    mut win: ResMut<WinConditions>,
+    mut fielded: ResMut<Fielded>,
+    mut game_over: ResMut<GameOverState>,
+) {
+    let conditions = if config.level.win.is_empty() {
+        mode.conditions()
+    } else {
+        config.level.win.clone()
+    };
+    *win = WinConditions { conditions };
+    *fielded = Fielded::default();
+    *game_over = GameOverState::default();
+}
+
 fn check_game_over(
     player_score: Res<PlayerScore>,
     ai_score: Res<AiScore>,
+    board: Res<Board>,
+    territories: Res<Territories>,
+    stability: Res<Stability>,
+    history: Res<History>,
+    stepped: Res<GenerationsStepped>,
+    win: Res<WinConditions>,
+    fielded: Res<Fielded>,
     mut best_score: ResMut<BestScore>,
     mut game_over: ResMut<GameOverState>,
     mut next_screen: ResMut<NextState<Screen>>,
@@ -32,15 +76,24 @@ fn check_game_over(
     if player_score.0 > best_score.0 {
         best_score.0 = player_score.0;
     }
+    // Only a generation step can end the match, not edits or rewinding.
+    if game_over.is_over || !stepped.any() {
+        return;
+    }
 
-    // Check win/lose conditions
-    if ai_score.0 < 1 && !game_over.is_over && player_score.0 >= 1 {
-        game_over.is_over = true;
-        game_over.message = "You win!".to_string();
-        next_screen.set(Screen::GameOver);
-    } else if player_score.0 < -100 && !game_over.is_over {
+    let view = MatchView {
+        board: &board,
+        territories: &territories,
+        stability: &stability,
+        generation: history.generation(),
+        player_score: player_score.0,
+        ai_score: ai_score.0,
+        fielded: *fielded,
+    };
+    if let Some(verdict) = judge(&win.conditions, &view) {
         game_over.is_over = true;
-        game_over.message = "You lose.".to_string();
+        game_over.message = verdict.message().to_string();
+        game_over.reason = verdict.reason;
         next_screen.set(Screen::GameOver);
     }
 }
@@ -51,6 +104,9 @@ fn show_game_over_ui(
     player_score: Res<PlayerScore>,
     ai_score: Res<AiScore>,
     best_score: Res<BestScore>,
+    kills: Res<Kills>,
+    territories: Res<Territories>,
+    rng: Res<GameRng>,
     mut commands: Commands,
 ) {
     if !game_over.is_over {
@@ -61,6 +117,7 @@ fn show_game_over_ui(
         .insert(GameOverUi)
         .with_children(|root| {
             root.spawn(build_text(game_over.message.as_str(), YELLOW.into(), 40.0));
+            root.spawn(build_text(game_over.reason.as_str(), Color::WHITE, 24.0));
             root.spawn(build_text(
                 &format!("Player Score: {}", player_score.0),
                 Color::WHITE,
@@ -71,11 +128,35 @@ fn show_game_over_ui(
                 Color::WHITE,
                 24.0,
             ));
+            root.spawn(build_text(
+                &format!(
+                    "Kills: {} by you, {} by the AI",
+                    kills.of(Owner::PLAYER),
+                    kills.of(Owner::AI)
+                ),
+                Color::WHITE,
+                24.0,
+            ));
+            root.spawn(build_text(
+                &format!(
+                    "Territory points: {} for you, {} for the AI",
+                    territories.points(Owner::PLAYER),
+                    territories.points(Owner::AI)
+                ),
+                Color::WHITE,
+                24.0,
+            ));
             root.spawn(build_text(
                 &format!("Best Score: {}", best_score.0),
                 Color::WHITE,
                 24.0,
             ));
+            // Lets a match be replayed with `--seed`.
+            root.spawn(build_text(
+                &format!("Seed: {}", rng.seed()),
+                Color::WHITE,
+                18.0,
+            ));
             root.spawn(widget::button("Back", go_back_on_click));
         });
 }
@@ -109,9 +190,15 @@ fn build_text(text: &str, color: Color, size: f32) -> (Text, TextFont, TextColor
 pub(super) fn plugin(app: &mut App) {
     app.insert_resource(BestScore::default())
         .insert_resource(GameOverState::default())
+        .init_resource::<GameMode>()
+        .init_resource::<WinConditions>()
+        .init_resource::<Fielded>()
+        .add_systems(OnEnter(Screen::Gameplay), start_match)
         .add_systems(
             FixedUpdate,
-            (check_game_over)
+            check_game_over
+                .after(game_of_life_step)
+                .run_if(in_state(Screen::Gameplay))
                 .in_set(AppSystems::Update)
                 .in_set(PausableSystems),
         )
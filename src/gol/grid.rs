@@ -1,99 +1,220 @@
-use super::cell::{Cell, CellState};
+use super::cell::CellState;
 use crate::{
     AppSystems, PausableSystems,
     gol::{
-        cell::{CellType, Outcome, RegionOwner},
-        pattern::Dir,
+        self,
+        board::Board,
+        cell::RegionOwner,
+        history::{History, Snapshot, Tally},
+        level::Level,
+        neighbourhood::Neighbourhood,
+        region::{Region, RegionLayout},
+        rng::GameRng,
+        rule::{Rule, Rules},
+        score::{Kills, WaterSpent},
+        speed::{GenerationsStepped, PendingGenerations, SimulationSpeed},
+        stability::Stability,
+        state::Playing,
+        stats::CellStats,
+        territory::{ControlRule, Territories},
+        topology::Topology,
+        tower::OverrunTowers,
+        win::Fielded,
     },
     screens::Screen,
 };
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
 use rand::prelude::*;
+use std::ops::Range;
 
-pub const GRID_WIDTH: usize = 64;
-pub const GRID_HEIGHT: usize = 64;
-pub const CELL_SIZE: f32 = 10.0;
+pub const DEFAULT_GRID_WIDTH: usize = 64;
+pub const DEFAULT_GRID_HEIGHT: usize = 64;
+pub const DEFAULT_CELL_SIZE: f32 = 10.0;
 
-const REGION_DEFAULT_HEIGHT: usize = GRID_HEIGHT / 5;
-const fn region_default_height() -> usize {
-    REGION_DEFAULT_HEIGHT
-}
-/// Convert grid coordinates to world coordinates using the same
-/// transformation that [`setup_grid`] applies when spawning cells.
-pub fn grid_to_world(x: usize, y: usize) -> Vec2 {
-    Vec2::new(
-        (x as f32 - GRID_WIDTH as f32 / 2.0) * CELL_SIZE,
-        (y as f32 - GRID_HEIGHT as f32 / 2.0) * CELL_SIZE,
-    )
-}
+/// Extra room around the grid when fitting the camera to it.
+const GRID_CAMERA_MARGIN: f32 = 1.1;
+
+/// Grid sizes offered by the settings menu, smallest first.
+pub const GRID_SIZE_PRESETS: &[(usize, usize)] = &[(32, 32), (64, 64), (128, 64), (256, 128)];
 
-pub struct Region {
-    pub bounds: Rect,
+/// Dimensions of the board and the size of a cell in world units.
+///
+/// Read by [`setup_grid`] when a match starts, so changes apply to the next
+/// match. Can be set on the command line with `--grid WIDTHxHEIGHT` and
+/// `--level PATH`.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct GridConfig {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+    /// The terrain of the map.
+    pub level: Level,
+    /// Where the player's and the AI's regions lie.
+    pub regions: RegionLayout,
 }
-pub struct WorldRegion {
-    pub bounds: Rect,
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_GRID_WIDTH,
+            height: DEFAULT_GRID_HEIGHT,
+            cell_size: DEFAULT_CELL_SIZE,
+            topology: Topology::default(),
+            neighbourhood: Neighbourhood::default(),
+            level: Level::default(),
+            regions: RegionLayout::default(),
+        }
+    }
 }
-impl Region {
-    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+
+impl GridConfig {
+    pub fn new(width: usize, height: usize) -> Self {
         Self {
-            bounds: Rect::new(x, y, width, height),
+            width,
+            height,
+            ..default()
         }
     }
-    pub fn from(dir: Dir, range_opt: Option<usize>) -> Self {
-        let range = range_opt.unwrap_or(REGION_DEFAULT_HEIGHT);
-        let mut bounds = Rect::new(0.0, 0.0, GRID_WIDTH as f32, GRID_HEIGHT as f32); // all
-        match dir {
-            Dir::S => {
-                bounds.max.y = range as f32;
-            }
-            Dir::N => {
-                bounds.min.y = GRID_HEIGHT as f32 - range as f32;
-            }
-            Dir::E => {
-                //to test
-                bounds.min.x = GRID_WIDTH as f32 - range as f32;
-            }
-            Dir::W => {
-                //to test
-                bounds.max.x = range as f32;
-            }
-            _ => {}
-        };
-        Self { bounds }
+
+    /// Reads `--grid WIDTHxHEIGHT` (or `--grid=WIDTHxHEIGHT`) from the
+    /// command line arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = if arg == "--grid" {
+                args.next()?
+            } else if let Some(value) = arg.strip_prefix("--grid=") {
+                value.to_string()
+            } else {
+                continue;
+            };
+            let Some((width, height)) = parse_size(&value) else {
+                warn!("Ignoring invalid grid size '{value}', expected WIDTHxHEIGHT");
+                return None;
+            };
+            return Some(Self::new(width, height));
+        }
+        None
     }
-    pub fn to_world(&self) -> WorldRegion {
-        let world_min = Vec2::new(
-            (self.bounds.min.x - GRID_WIDTH as f32 / 2.0) * CELL_SIZE,
-            (self.bounds.min.y - GRID_HEIGHT as f32 / 2.0) * CELL_SIZE,
-        );
-        let world_max = Vec2::new(
-            (self.bounds.max.x - GRID_WIDTH as f32 / 2.0) * CELL_SIZE,
-            (self.bounds.max.y - GRID_HEIGHT as f32 / 2.0) * CELL_SIZE,
-        );
-        let result = WorldRegion {
-            bounds: Rect {
-                min: world_min,
-                max: world_max,
-            },
-        };
-        info!("Region to world: {:?} -> {:?}", self.bounds, result.bounds);
-        result
+
+    /// Height of the default player and AI bands at the bottom and top.
+    pub fn region_default_height(&self) -> usize {
+        self.height / 5
     }
-}
 
-impl WorldRegion {
-    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
-        Self {
-            bounds: Rect::new(x, y, width, height),
+    /// Width of bands along the left and right edges.
+    pub fn region_default_width(&self) -> usize {
+        self.width / 5
+    }
+
+    /// The cells starting out as `owner`'s, in the configured
+    /// [`RegionLayout`].
+    pub fn region(&self, owner: RegionOwner) -> Region {
+        self.regions.region(owner, self)
+    }
+
+    /// Size of the whole grid in world units. Hexagonal grids are half a
+    /// cell wider, for the shifted odd rows.
+    pub fn world_size(&self) -> Vec2 {
+        let width = self.width as f32 + self.row_shift(1);
+        Vec2::new(width * self.cell_size, self.height as f32 * self.cell_size)
+    }
+
+    /// How far the cells of row `y` are shifted to the right, in cells.
+    pub fn row_shift(&self, y: i32) -> f32 {
+        if self.neighbourhood.is_hexagonal() && y % 2 != 0 {
+            0.5
+        } else {
+            0.0
         }
     }
-    pub fn to_random_pos(&self) -> Vec2 {
-        let x = rand::thread_rng().gen_range(self.bounds.min.x..self.bounds.max.x);
-        let y = rand::thread_rng().gen_range(self.bounds.min.y..self.bounds.max.y);
-        Vec2::new(x, y)
+
+    /// World position of the centre of the cell at grid coordinates `x, y`.
+    pub fn grid_to_world(&self, x: usize, y: usize) -> Vec2 {
+        let shift = self.row_shift(y as i32);
+        self.grid_to_world_f32(Vec2::new(x as f32 + shift, y as f32))
+    }
+
+    /// Like [`GridConfig::grid_to_world`] but for fractional grid positions,
+    /// e.g. the corners of a cell.
+    pub fn grid_to_world_f32(&self, grid: Vec2) -> Vec2 {
+        Vec2::new(
+            (grid.x - self.width as f32 / 2.0) * self.cell_size,
+            (grid.y - self.height as f32 / 2.0) * self.cell_size,
+        )
+    }
+
+    /// Grid coordinates of the cell containing `world`, if it is on the grid.
+    pub fn world_to_grid(&self, world: Vec2) -> Option<(usize, usize)> {
+        let pos = self.world_to_grid_unbounded(world);
+        let in_bounds = pos.x >= 0
+            && pos.y >= 0
+            && (pos.x as usize) < self.width
+            && (pos.y as usize) < self.height;
+        in_bounds.then_some((pos.x as usize, pos.y as usize))
+    }
+
+    /// Grid coordinates of the cell containing `world`, which may lie
+    /// beyond the edges of the grid.
+    pub fn world_to_grid_unbounded(&self, world: Vec2) -> IVec2 {
+        let y = (world.y / self.cell_size + self.height as f32 / 2.0).round() as i32;
+        let x = world.x / self.cell_size + self.width as f32 / 2.0 - self.row_shift(y);
+        IVec2::new(x.round() as i32, y)
+    }
+
+    /// The cells whose centres lie in the world rectangle from `min` to `max`,
+    /// row by row. The columns of the shifted odd rows of a hexagonal grid
+    /// are picked with the shift.
+    pub fn world_rect_to_grid(
+        &self,
+        min: Vec2,
+        max: Vec2,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rows = self.centres_between(min.y, max.y, self.height, 0.0);
+        rows.flat_map(move |y| {
+            let shift = self.row_shift(y as i32);
+            let columns = self.centres_between(min.x, max.x, self.width, shift);
+            columns.map(move |x| (x, y))
+        })
+    }
+
+    /// The indices of the cells along one axis, `len` long and shifted by
+    /// `shift` cells, whose centres lie between world coordinates `min` and
+    /// `max`. Empty when the span misses the grid.
+    fn centres_between(&self, min: f32, max: f32, len: usize, shift: f32) -> Range<usize> {
+        let half = len as f32 / 2.0 - shift;
+        let start = (min / self.cell_size + half).ceil().clamp(0.0, len as f32) as usize;
+        let end = (max / self.cell_size + half).floor() + 1.0;
+        start..(end.clamp(0.0, len as f32) as usize).max(start)
+    }
+
+    /// Where a pattern cell at grid position `pos` lands, honouring the
+    /// configured [`Topology`].
+    pub fn place(&self, pos: IVec2) -> Option<(usize, usize)> {
+        self.topology.place(pos.x, pos.y, self.width, self.height)
+    }
+
+    /// The next entry of [`GRID_SIZE_PRESETS`] after the current size,
+    /// wrapping around. `step` of -1 goes backwards.
+    pub fn cycle_preset(&mut self, step: i32) {
+        let (width, height) = gol::cycle(GRID_SIZE_PRESETS, (self.width, self.height), step);
+        self.width = width;
+        self.height = height;
     }
 }
 
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once(['x', 'X'])?;
+    let width = width.trim().parse().ok()?;
+    let height = height.trim().parse().ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
 enum InitialCellState {
     Dead,
     Ramdom,
@@ -101,15 +222,24 @@ enum InitialCellState {
 
 const INITIAL_CELL_STATE: InitialCellState = InitialCellState::Dead;
 
-pub fn setup_grid(mut commands: Commands) {
-    let mut rng = rand::thread_rng();
+pub fn setup_grid(
+    mut board: ResMut<Board>,
+    config: Res<GridConfig>,
+    rules: Res<Rules>,
+    mut rng: ResMut<GameRng>,
+) {
+    *board = Board::new(config.width, config.height)
+        .with_topology(config.topology)
+        .with_neighbourhood(config.neighbourhood);
+    let player = config.region(RegionOwner::Player);
+    let ai = config.region(RegionOwner::AI);
 
-    for y in 0..GRID_HEIGHT {
-        for x in 0..GRID_WIDTH {
+    for y in 0..config.height {
+        for x in 0..config.width {
             let state = match INITIAL_CELL_STATE {
                 InitialCellState::Dead => CellState::Dead,
                 InitialCellState::Ramdom => {
-                    if rng.gen_bool(0.2) {
+                    if rng.rules.gen_bool(0.2) {
                         CellState::default_alive()
                     } else {
                         CellState::Dead
@@ -117,141 +247,177 @@ pub fn setup_grid(mut commands: Commands) {
                 }
             };
 
-            let region_default_height = GRID_HEIGHT / 5;
             // Assign region
-            let region = if y >= GRID_HEIGHT - region_default_height {
+            let region = if player.contains(x, y) {
                 RegionOwner::Player
-            } else if y < region_default_height {
+            } else if ai.contains(x, y) {
                 RegionOwner::AI
             } else {
                 RegionOwner::None
             };
-
-            commands.spawn((
-                StateScoped(Screen::Gameplay),
-                Sprite {
-                    color: state.color(),
-                    custom_size: Some(Vec2::splat(CELL_SIZE)),
-                    ..Default::default()
-                },
-                Transform::from_xyz(
-                    (x as f32 - GRID_WIDTH as f32 / 2.0) * CELL_SIZE,
-                    (y as f32 - GRID_HEIGHT as f32 / 2.0) * CELL_SIZE,
-                    0.0,
-                ),
-                Cell {
-                    x,
-                    y,
-                    state,
-                    region,
-                },
-            ));
+            // Elements without a side are sown in the unowned middle.
+            let state = match region {
+                RegionOwner::None => rules
+                    .elements
+                    .kinds()
+                    .find(|(_, element)| {
+                        element.scatter > 0.0 && rng.rules.gen_bool(element.scatter as f64)
+                    })
+                    .map_or(state, |(kind, _)| CellState::Alive(kind)),
+                _ => state,
+            };
+            board.set(x, y, state);
+            board.set_region(x, y, region);
+            if region != RegionOwner::None && state.is_alive() {
+                board.set_owner(x, y, region.into());
+            }
         }
     }
-}
-
-fn game_of_life_step(mut query: Query<(&mut Sprite, &mut Cell)>) {
-    let mut grid = vec![vec![CellState::DeadPlain; GRID_WIDTH]; GRID_HEIGHT];
 
-    // Copy current state
-    for cell in query.iter() {
-        grid[cell.1.y][cell.1.x] = cell.1.state;
+    let terrain = config.level.terrain_for(config.width, config.height);
+    for (i, terrain) in terrain.into_iter().enumerate() {
+        board.set_terrain(i % config.width, i / config.width, terrain);
     }
+}
 
-    for (mut sprite, mut cell) in query.iter_mut() {
-        let mut alive_neighbors = Vec::<CellType>::new();
-        for dy in -1i32..=1 {
-            for dx in -1i32..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = cell.x as i32 + dx;
-                let ny = cell.y as i32 + dy;
-                if nx >= 0
-                    && ny >= 0
-                    && nx < GRID_WIDTH as i32
-                    && ny < GRID_HEIGHT as i32
-                    && grid[ny as usize][nx as usize].is_alive()
-                {
-                    alive_neighbors.push(grid[ny as usize][nx as usize].kind());
-                }
-            }
-        }
-
-        let next_state = match (cell.state.is_alive(), alive_neighbors.len()) {
-            (true, 2..=3) => alive_state_from(cell.state.kind(), &alive_neighbors),
-
-            (false, 3) => alive_state_from(most_frequent_kind(&alive_neighbors), &alive_neighbors),
-            _ => CellState::Dead,
-        };
-
-        if next_state != cell.state {
-            sprite.color = next_state.color();
-            cell.state = next_state;
-        }
+/// Zooms the camera so the whole grid fits in the window.
+fn fit_camera_to_grid(
+    config: Res<GridConfig>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut projection: Single<&mut Projection, With<Camera2d>>,
+) {
+    let Projection::Orthographic(ortho) = projection.as_mut() else {
+        return;
+    };
+    let world = config.world_size() * GRID_CAMERA_MARGIN;
+    let window_size = window.size();
+    if window_size.x <= 0.0 || window_size.y <= 0.0 {
+        return;
     }
+    ortho.scale = (world.x / window_size.x).max(world.y / window_size.y);
 }
 
-fn most_frequent_kind(alive_neighbors: &Vec<CellType>) -> CellType {
-    if alive_neighbors.is_empty() {
-        warn!("No alive neighbors found, returning default CellType::PlainOn");
-        return CellType::PlainOn;
+/// Advances the [`Board`] by the generations due this tick.
+///
+/// While playing that is the current [`SimulationSpeed`], while paused the
+/// generations queued by single-stepping.
+pub fn game_of_life_step(
+    mut board: ResMut<Board>,
+    mut history: ResMut<History>,
+    mut stats: ResMut<CellStats>,
+    mut stability: ResMut<Stability>,
+    mut kills: ResMut<Kills>,
+    mut territories: ResMut<Territories>,
+    mut fielded: ResMut<Fielded>,
+    overrun_towers: Res<OverrunTowers>,
+    control: Res<ControlRule>,
+    rules: Res<Rules>,
+    playing: Res<Playing>,
+    speed: Res<SimulationSpeed>,
+    mut pending: ResMut<PendingGenerations>,
+    mut stepped: ResMut<GenerationsStepped>,
+    spent: Res<WaterSpent>,
+    mut rng: ResMut<GameRng>,
+) {
+    let generations = if playing.0 {
+        speed.level().generations
+    } else {
+        std::mem::take(&mut pending.0)
+    };
+    stepped.from = history.generation();
+    stepped.to = stepped.from;
+    if generations == 0 {
+        return;
     }
-    let mut counts = std::collections::HashMap::new();
 
-    for &kind in alive_neighbors {
-        *counts.entry(kind).or_insert(0) += 1;
+    let tally = |kills: &Kills,
+                 stability: &Stability,
+                 territories: &Territories,
+                 fielded: &Fielded| Tally {
+        water_spent: *spent,
+        kills: kills.clone(),
+        stability: stability.clone(),
+        territories: territories.tally(),
+        fielded: *fielded,
+        overrun_towers: overrun_towers.clone(),
+    };
+    // Patterns placed since the last tick and the like.
+    fielded.record(&board);
+    history.record_edits(
+        &board,
+        &rng,
+        &tally(&kills, &stability, &territories, &fielded),
+    );
+    for _ in 0..generations {
+        let before = Snapshot::of(&board);
+        board.step(&rules, &mut rng.rules);
+        stats.record(before.cells(), board.cells());
+        stability.record(board.cells());
+        kills.record(&before, &board);
+        territories.record(&board, &control);
+        fielded.record(&board);
+        history.record(
+            &board,
+            &rng,
+            &tally(&kills, &stability, &territories, &fielded),
+        );
     }
+    stepped.to = history.generation();
+}
 
-    counts
-        .into_iter()
-        .max_by_key(|&(_, count)| count)
-        .map_or(CellType::Empty, |(kind, _)| kind)
+pub(super) fn plugin(app: &mut App) {
+    let mut config = GridConfig::from_args(std::env::args()).unwrap_or_default();
+    config.level = Level::from_args(std::env::args()).unwrap_or_default();
+    app.insert_resource(Time::<Fixed>::from_seconds(
+        SimulationSpeed::default().level().seconds,
+    ))
+    .insert_resource(Board::new(config.width, config.height))
+    .insert_resource(config)
+    .insert_resource(Rules {
+        rule: Rule::from_args(std::env::args()).unwrap_or_default(),
+        ..default()
+    })
+    .add_systems(OnEnter(Screen::Gameplay), fit_camera_to_grid)
+    .add_systems(
+        Update,
+        fit_camera_to_grid.run_if(in_state(Screen::Gameplay).and(on_event::<WindowResized>)),
+    )
+    .add_systems(
+        FixedUpdate,
+        game_of_life_step
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
 }
 
-fn alive_state_from(
-    current_kind_or_most_alive: CellType,
-    alive_neighbors: &[CellType],
-) -> CellState {
-    if alive_neighbors.is_empty() {
-        return CellState::Alive(current_kind_or_most_alive);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_rect_picks_the_cells_whose_centres_it_covers() {
+        let config = GridConfig::new(8, 8);
+        let min = config.grid_to_world(2, 3) - 0.4 * config.cell_size;
+        let max = config.grid_to_world(4, 5) + 0.4 * config.cell_size;
+        let cells: Vec<_> = config.world_rect_to_grid(min, max).collect();
+        let expected: Vec<_> = (3..=5).flat_map(|y| (2..=4).map(move |x| (x, y))).collect();
+        assert_eq!(cells, expected);
     }
-    let mut rng = rand::thread_rng();
-
-    //top two kinds by frequency in alive_neighbors plus current_kind_or_most_alive
-    let top_two_kinds: (CellType, CellType) = alive_neighbors.iter().fold(
-        (current_kind_or_most_alive, current_kind_or_most_alive),
-        |(a, b), &neighbor| {
-            if neighbor == a || neighbor == b {
-                (a, b)
-            } else if rng.gen_bool(0.5) {
-                (neighbor, a)
-            } else {
-                (a, neighbor)
-            }
-        },
-    );
-    let next_kind = match top_two_kinds.0.battle(&top_two_kinds.1) {
-        Outcome::Win => top_two_kinds.0,
-        Outcome::Lose => top_two_kinds.1,
-        Outcome::Draw => {
-            if rng.gen_bool(0.5) {
-                top_two_kinds.0
-            } else {
-                top_two_kinds.1
-            }
-        }
-    };
 
-    CellState::Alive(next_kind)
-}
+    #[test]
+    fn world_rect_shifts_the_odd_rows_of_a_hexagonal_grid() {
+        let config = GridConfig {
+            neighbourhood: Neighbourhood::Hexagonal,
+            ..GridConfig::new(8, 8)
+        };
+        // Across the rows 2 and 3 from the centre of (2, 3), half a cell
+        // right of (2, 2), to the centre of (4, 2).
+        let min = Vec2::new(config.grid_to_world(2, 3).x, config.grid_to_world(4, 2).y);
+        let max = Vec2::new(config.grid_to_world(4, 2).x, config.grid_to_world(2, 3).y);
+        let cells: Vec<_> = config.world_rect_to_grid(min, max).collect();
+        assert_eq!(cells, [(3, 2), (4, 2), (2, 3), (3, 3)]);
 
-pub(super) fn plugin(app: &mut App) {
-    app.insert_resource(Time::<Fixed>::from_seconds(0.2))
-        .add_systems(
-            FixedUpdate,
-            game_of_life_step
-                .in_set(AppSystems::Update)
-                .in_set(PausableSystems),
-        );
+        let outside = Vec2::splat(-100.0 * config.cell_size);
+        assert_eq!(config.world_rect_to_grid(outside, outside).count(), 0);
+    }
 }
@@ -0,0 +1,415 @@
+//! HashLife for a single element under one B/S [`Rule`].
+//!
+//! The world is a quadtree whose identical subtrees are shared, and the
+//! future of each subtree is memoised, so repetitive patterns like a glider
+//! gun can be advanced 2^n generations in about the time of a few steps.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::gol::rule::Rule;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// A square of `2^level` cells on a side. Children are ordered
+/// `[low x low y, high x low y, low x high y, high x high y]`.
+#[derive(Clone, Copy)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+    population: u64,
+}
+
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    /// Empty node of each level.
+    empty: Vec<NodeId>,
+    /// Centre of a node advanced `2^j` generations, keyed by `(node, j)`.
+    results: HashMap<(NodeId, u8), NodeId>,
+    root: NodeId,
+    /// World position of the root's low corner.
+    origin: IVec2,
+}
+
+impl HashLife {
+    /// A world where exactly `cells` are alive.
+    pub fn new(rule: Rule, cells: impl IntoIterator<Item = IVec2>) -> Self {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut life = Self {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            interned: HashMap::new(),
+            empty: vec![DEAD],
+            results: HashMap::new(),
+            root: DEAD,
+            origin: IVec2::ZERO,
+        };
+        let cells: Vec<IVec2> = cells.into_iter().collect();
+        let (Some(min), Some(max)) = (
+            cells.iter().copied().reduce(IVec2::min),
+            cells.iter().copied().reduce(IVec2::max),
+        ) else {
+            life.root = life.empty(3);
+            return life;
+        };
+        let span = (max - min).max_element() as u32 + 1;
+        let level = (span.next_power_of_two().trailing_zeros() as u8).max(3);
+        life.origin = min;
+        life.root = life.build(level, min, &cells);
+        life
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Advances the world `2^j` generations.
+    pub fn jump(&mut self, j: u8) {
+        // Keep the pattern in the middle quarter, and the root big enough
+        // that nothing can reach past the centre that `successor` returns.
+        while self.level() < j + 3 || !self.is_padded() {
+            self.expand();
+        }
+        let level = self.level();
+        self.root = self.successor(self.root, j);
+        self.origin += IVec2::splat(1 << (level - 2));
+    }
+
+    /// Calls `f` with the position of every live cell.
+    pub fn for_each_alive(&self, mut f: impl FnMut(IVec2)) {
+        self.visit(self.root, self.origin, &mut f);
+    }
+
+    fn level(&self) -> u8 {
+        self.nodes[self.root as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+        let level = self.nodes[children[0] as usize].level + 1;
+        let population = children
+            .iter()
+            .map(|&c| self.nodes[c as usize].population)
+            .sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level,
+            children,
+            population,
+        });
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap_or(&DEAD);
+            let id = self.join([below; 4]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    fn build(&mut self, level: u8, origin: IVec2, cells: &[IVec2]) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+        let half = 1 << (level - 1);
+        let mut quadrants: [Vec<IVec2>; 4] = Default::default();
+        for &cell in cells {
+            let high = cell - origin;
+            let q = usize::from(high.x >= half) + 2 * usize::from(high.y >= half);
+            quadrants[q].push(cell);
+        }
+        let children = std::array::from_fn(|q| {
+            let offset = IVec2::new(half * (q as i32 % 2), half * (q as i32 / 2));
+            self.build(level - 1, origin + offset, &quadrants[q])
+        });
+        self.join(children)
+    }
+
+    /// Surrounds the root with empty space, doubling its size.
+    fn expand(&mut self) {
+        let level = self.level();
+        let empty = self.empty(level - 1);
+        let [a, b, c, d] = self.children(self.root);
+        let children = [
+            self.join([empty, empty, empty, a]),
+            self.join([empty, empty, b, empty]),
+            self.join([empty, c, empty, empty]),
+            self.join([d, empty, empty, empty]),
+        ];
+        self.root = self.join(children);
+        self.origin -= IVec2::splat(1 << (level - 1));
+    }
+
+    /// Whether every live cell is in the middle quarter of the root.
+    fn is_padded(&self) -> bool {
+        if self.level() < 3 {
+            return false;
+        }
+        let population = |id: NodeId| self.nodes[id as usize].population;
+        let [a, b, c, d] = self.children(self.root);
+        // The grandchild of each quadrant that touches the centre.
+        population(a) == population(self.children(self.children(a)[3])[3])
+            && population(b) == population(self.children(self.children(b)[2])[2])
+            && population(c) == population(self.children(self.children(c)[1])[1])
+            && population(d) == population(self.children(self.children(d)[0])[0])
+    }
+
+    /// The centre of `id` (one level down) advanced `2^j` generations, with
+    /// `j` at most `level - 2`.
+    fn successor(&mut self, id: NodeId, j: u8) -> NodeId {
+        let level = self.nodes[id as usize].level;
+        let j = j.min(level - 2);
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+        let result = if self.nodes[id as usize].population == 0 {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.step_4x4(id)
+        } else {
+            // The nine overlapping sub-squares one level down.
+            let [a, b, c, d] = self.children(id);
+            let [aa, ab, ac, ad] = self.children(a);
+            let [ba, bb, bc, bd] = self.children(b);
+            let [ca, cb, cc, cd] = self.children(c);
+            let [da, db, dc, dd] = self.children(d);
+            let squares = [
+                [aa, ab, ac, ad],
+                [ab, ba, ad, bc],
+                [ba, bb, bc, bd],
+                [ac, ad, ca, cb],
+                [ad, bc, cb, da],
+                [bc, bd, da, db],
+                [ca, cb, cc, cd],
+                [cb, da, cd, dc],
+                [da, db, dc, dd],
+            ];
+            let mut c9 = [DEAD; 9];
+            for (result, square) in c9.iter_mut().zip(squares) {
+                let node = self.join(square);
+                *result = self.successor(node, j);
+            }
+            let quads = [
+                [c9[0], c9[1], c9[3], c9[4]],
+                [c9[1], c9[2], c9[4], c9[5]],
+                [c9[3], c9[4], c9[6], c9[7]],
+                [c9[4], c9[5], c9[7], c9[8]],
+            ];
+            let mut children = [DEAD; 4];
+            for (child, quad) in children.iter_mut().zip(quads) {
+                *child = if j < level - 2 {
+                    // Already far enough: take the centre of the four.
+                    let [w, x, y, z] = quad.map(|id| self.children(id));
+                    self.join([w[3], x[2], y[1], z[0]])
+                } else {
+                    let node = self.join(quad);
+                    self.successor(node, j)
+                };
+            }
+            self.join(children)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// One generation of the centre 2x2 of a 4x4 node.
+    fn step_4x4(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (q, &child) in self.children(id).iter().enumerate() {
+            for (r, &leaf) in self.children(child).iter().enumerate() {
+                let x = 2 * (q % 2) + r % 2;
+                let y = 2 * (q / 2) + r / 2;
+                cells[y][x] = leaf == ALIVE;
+            }
+        }
+        let mut next = [DEAD; 4];
+        for (r, leaf) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + r % 2, 1 + r / 2);
+            let count = cells[y - 1..=y + 1]
+                .iter()
+                .flat_map(|row| &row[x - 1..=x + 1])
+                .filter(|&&alive| alive)
+                .count()
+                - usize::from(cells[y][x]);
+            let alive = if cells[y][x] {
+                self.rule.survives(count)
+            } else {
+                self.rule.births(count)
+            };
+            *leaf = if alive { ALIVE } else { DEAD };
+        }
+        self.join(next)
+    }
+
+    fn visit(&self, id: NodeId, origin: IVec2, f: &mut impl FnMut(IVec2)) {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            f(origin);
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        for (q, &child) in node.children.iter().enumerate() {
+            let offset = IVec2::new(half * (q as i32 % 2), half * (q as i32 / 2));
+            self.visit(child, origin + offset, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::gol::{
+        board::Board,
+        cell::{CellState, CellType},
+        rule::Rules,
+        sparse::SparseWorld,
+        topology::Topology,
+    };
+
+    const GLIDER: &str = "
+        .#.
+        ..#
+        ###";
+
+    const GOSPER_GUN: &str = "
+        ........................#...........
+        ......................#.#...........
+        ............##......##............##
+        ...........#...#....##............##
+        ##........#.....#...##..............
+        ##........#...#.##....#.#...........
+        ..........#.....#.......#...........
+        ...........#...#....................
+        ............##......................";
+
+    /// The `#` cells of `picture`, top row first, moved well away from the
+    /// grid of a one-cell board so they only live in the world around it.
+    fn far_away(picture: &str) -> Vec<IVec2> {
+        let rows: Vec<&str> = picture.trim().lines().map(str::trim).collect();
+        let mut cells = Vec::new();
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    cells.push(IVec2::new(x as i32, y as i32) + 100);
+                }
+            }
+        }
+        cells
+    }
+
+    fn sorted(mut cells: Vec<IVec2>) -> Vec<IVec2> {
+        cells.sort_by_key(|pos| (pos.y, pos.x));
+        cells
+    }
+
+    fn world_of(cells: &[IVec2], kind: CellType) -> SparseWorld {
+        let mut world = SparseWorld::default();
+        for &pos in cells {
+            world.set(pos, CellState::Alive(kind));
+        }
+        world
+    }
+
+    /// `cells` after `generations` scalar steps of an unbounded board.
+    fn stepped(cells: &[IVec2], generations: usize) -> Vec<IVec2> {
+        let rules = Rules::default();
+        let mut board = Board::new(1, 1).with_topology(Topology::Unbounded);
+        *board.world_mut().unwrap() = world_of(cells, CellType::TREE);
+        let rng = &mut StdRng::seed_from_u64(0);
+        for _ in 0..generations {
+            board.step_scalar(&rules, rng);
+        }
+        sorted(board.world().unwrap().alive().map(|(pos, _)| pos).collect())
+    }
+
+    fn jumped(cells: &[IVec2], j: u8) -> Vec<IVec2> {
+        let mut life = HashLife::new(Rules::default().rule, cells.iter().copied());
+        life.jump(j);
+        let mut alive = Vec::new();
+        life.for_each_alive(|pos| alive.push(pos));
+        sorted(alive)
+    }
+
+    #[test]
+    fn jumps_like_the_scalar_step() {
+        for (name, cells) in [("glider", far_away(GLIDER)), ("gun", far_away(GOSPER_GUN))] {
+            for j in 0..=6 {
+                let expected = stepped(&cells, 1 << j);
+                assert!(!expected.is_empty());
+                assert_eq!(jumped(&cells, j), expected, "{name} jumping 2^{j}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_gun_fires_a_glider_every_30_generations() {
+        let cells = far_away(GOSPER_GUN);
+        assert_eq!(stepped(&cells, 30).len(), cells.len() + 5);
+        assert_eq!(stepped(&cells, 60).len(), cells.len() + 10);
+    }
+
+    #[test]
+    fn an_empty_world_stays_empty() {
+        let mut life = HashLife::new(Rules::default().rule, []);
+        life.jump(10);
+        assert_eq!(life.population(), 0);
+        assert_eq!(stepped(&[], 4), []);
+    }
+
+    #[test]
+    fn sparse_worlds_jump_like_the_scalar_step() {
+        let cells = far_away(GLIDER);
+        let mut world = world_of(&cells, CellType::TREE);
+        assert!(world.jump(4, &Rules::default()));
+        let alive = sorted(world.alive().map(|(pos, _)| pos).collect());
+        assert_eq!(alive, stepped(&cells, 16));
+    }
+
+    #[test]
+    fn sparse_worlds_of_several_elements_or_decay_dont_jump() {
+        let cells = far_away(GLIDER);
+        let mut mixed = world_of(&cells, CellType::TREE);
+        mixed.set(cells[0], CellState::Alive(CellType::FIRE));
+        let before = mixed.clone();
+        assert!(!mixed.jump(4, &Rules::default()));
+        assert!(mixed == before);
+
+        // Trees leave stumps behind.
+        let decay = Rules {
+            decay: true,
+            ..Rules::default()
+        };
+        let mut trees = world_of(&cells, CellType::TREE);
+        assert!(!trees.jump(4, &decay));
+        // Water is gone at once, so it still jumps.
+        let mut water = world_of(&cells, CellType::WATER);
+        assert!(water.jump(4, &decay));
+    }
+}
@@ -0,0 +1,757 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    AppSystems, PausableSystems,
+    gol::{
+        board::Board,
+        cell::{CellState, Owner},
+        player::populate_player_region,
+        rng::GameRng,
+        score::{Kills, WaterSpent},
+        sparse::{SparseWorld, WorldDiff},
+        speed::PendingGenerations,
+        stability::Stability,
+        state::Playing,
+        stats::CellStats,
+        terrain::Terrain,
+        territory::{Territories, TerritoryTally},
+        tower::{OverrunTowers, spawn_towers},
+        win::Fielded,
+    },
+    screens::Screen,
+};
+
+/// How many generations, and edits between them, can be stepped back.
+pub const HISTORY_CAPACITY: usize = 1000;
+/// Generations skipped by a shift+arrow step.
+const BIG_STEP: usize = 10;
+
+const TIMELINE_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+const TIMELINE_FILL_COLOR: Color = Color::srgb(0.8, 0.8, 0.5); // yellowish grey
+
+/// The cells of a board, who owns them and the terrain under them, taken
+/// before a step to record it against.
+#[derive(Default)]
+pub struct Snapshot {
+    cells: Vec<CellState>,
+    owners: Vec<Owner>,
+    terrain: Vec<Terrain>,
+}
+
+impl Snapshot {
+    pub fn of(board: &Board) -> Self {
+        Self {
+            cells: board.cells().to_vec(),
+            owners: board.owners().to_vec(),
+            terrain: board.terrains().to_vec(),
+        }
+    }
+
+    /// Whether `board` is still as it was when the snapshot was taken.
+    fn matches(&self, board: &Board) -> bool {
+        self.cells == board.cells()
+            && self.owners == board.owners()
+            && self.terrain == board.terrains()
+    }
+
+    pub fn cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    pub fn owners(&self) -> &[Owner] {
+        &self.owners
+    }
+}
+
+/// What the match has counted up to a generation, kept with it so
+/// rewinding the board rewinds the counts too.
+#[derive(Clone, Default)]
+pub struct Tally {
+    pub water_spent: WaterSpent,
+    pub kills: Kills,
+    pub stability: Stability,
+    pub territories: TerritoryTally,
+    pub fielded: Fielded,
+    pub overrun_towers: OverrunTowers,
+}
+
+/// The cells that changed between two recorded generations, as
+/// `(index, before, after)`.
+struct Diff {
+    changes: Vec<(usize, CellState, CellState)>,
+    /// Owners that changed, likewise.
+    owners: Vec<(usize, Owner, Owner)>,
+    /// Terrain that changed, likewise.
+    terrain: Vec<(usize, Terrain, Terrain)>,
+    /// Chunks of the world around an unbounded grid that changed.
+    world: WorldDiff,
+    /// Generations between the two, more than one after a jump and none
+    /// for edits made between generations.
+    generations: u64,
+    /// The dice as they were after the change, so a match resumed from
+    /// here rolls the same as it did the first time.
+    rng: GameRng,
+    /// What had been counted after the change.
+    tally: Tally,
+}
+
+impl Diff {
+    /// Puts the `before` side of the changes back in `board`.
+    fn undo(&self, board: &mut Board) {
+        for &(i, before, _) in &self.changes {
+            board.cells_mut()[i] = before;
+        }
+        for &(i, before, _) in &self.owners {
+            board.owners_mut()[i] = before;
+        }
+        for &(i, before, _) in &self.terrain {
+            board.terrains_mut()[i] = before;
+        }
+        if let Some(world) = board.world_mut() {
+            self.world.undo(world);
+        }
+    }
+
+    /// Puts the `after` side of the changes back in `board`.
+    fn redo(&self, board: &mut Board) {
+        for &(i, _, after) in &self.changes {
+            board.cells_mut()[i] = after;
+        }
+        for &(i, _, after) in &self.owners {
+            board.owners_mut()[i] = after;
+        }
+        for &(i, _, after) in &self.terrain {
+            board.terrains_mut()[i] = after;
+        }
+        if let Some(world) = board.world_mut() {
+            self.world.redo(world);
+        }
+    }
+}
+
+/// Bounded history of past generations, stored as diffs between them.
+///
+/// The board can be rewound to any recorded generation and replayed
+/// forward again; resuming the simulation from a rewound generation
+/// discards the generations after it. Edits made between generations, such
+/// as placed patterns, are recorded as diffs of their own that cover no
+/// generations, so stepping back undoes them one at a time.
+#[derive(Resource)]
+pub struct History {
+    capacity: usize,
+    diffs: VecDeque<Diff>,
+    /// Generation number of the latest recorded board.
+    latest: u64,
+    /// How many diffs behind the latest one the board is.
+    rewound: usize,
+    /// The board as of the diff it is at, which changes are recorded
+    /// against.
+    recorded: Snapshot,
+    /// The world around an unbounded board likewise; empty for others.
+    recorded_world: SparseWorld,
+    /// The dice before the oldest diff.
+    start_rng: Option<GameRng>,
+    /// What had been counted before the oldest diff.
+    start_tally: Tally,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HISTORY_CAPACITY)
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            diffs: VecDeque::with_capacity(capacity),
+            latest: 0,
+            rewound: 0,
+            recorded: Snapshot::default(),
+            recorded_world: SparseWorld::default(),
+            start_rng: None,
+            start_tally: Tally::default(),
+        }
+    }
+
+    /// Generation number of the board as it is now.
+    pub fn generation(&self) -> u64 {
+        self.latest - self.rewound_generations()
+    }
+
+    /// Generations covered by the rewound diffs.
+    fn rewound_generations(&self) -> u64 {
+        self.diffs
+            .iter()
+            .rev()
+            .take(self.rewound)
+            .map(|diff| diff.generations)
+            .sum()
+    }
+
+    pub fn latest_generation(&self) -> u64 {
+        self.latest
+    }
+
+    /// Number of diffs that can be stepped back from the latest one.
+    pub fn len(&self) -> usize {
+        self.diffs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diffs.is_empty()
+    }
+
+    pub fn rewound(&self) -> usize {
+        self.rewound
+    }
+
+    pub fn is_rewound(&self) -> bool {
+        self.rewound > 0
+    }
+
+    /// Forgets every recorded generation and starts over from `board` and
+    /// `rng` as generation 0, with nothing counted yet.
+    pub fn reset(&mut self, board: &Board, rng: &GameRng) {
+        self.diffs.clear();
+        self.latest = 0;
+        self.rewound = 0;
+        self.recorded = Snapshot::of(board);
+        self.recorded_world = world_of(board);
+        self.start_rng = Some(rng.clone());
+        self.start_tally = Tally::default();
+    }
+
+    /// Records one generation step to `board`, with the dice and the
+    /// tally as they were after it. Stepping from a rewound board drops the
+    /// generations that came after it.
+    pub fn record(&mut self, board: &Board, rng: &GameRng, tally: &Tally) {
+        self.record_jump(board, rng, tally, 1);
+    }
+
+    /// Like [`History::record`] for a jump of several generations at once,
+    /// which is stepped back and forward as one.
+    pub fn record_jump(&mut self, board: &Board, rng: &GameRng, tally: &Tally, generations: u64) {
+        self.resume();
+        if self.recorded.cells.len() != board.cells().len() {
+            // Nothing recorded for a board of this size yet.
+            self.reset(board, rng);
+            return;
+        }
+        if self.diffs.len() == self.capacity
+            && let Some(oldest) = self.diffs.pop_front()
+        {
+            self.start_rng = Some(oldest.rng);
+            self.start_tally = oldest.tally;
+        }
+        let after = Snapshot::of(board);
+        let world = world_of(board);
+        self.diffs.push_back(Diff {
+            changes: changes(&self.recorded.cells, &after.cells),
+            owners: changes(&self.recorded.owners, &after.owners),
+            terrain: changes(&self.recorded.terrain, &after.terrain),
+            world: WorldDiff::between(&self.recorded_world, &world),
+            generations,
+            rng: rng.clone(),
+            tally: tally.clone(),
+        });
+        self.recorded = after;
+        self.recorded_world = world;
+        self.latest += generations;
+    }
+
+    /// Records the edits made to `board` since it was last recorded, such
+    /// as placed patterns or a cleared board, as a diff of no generations.
+    /// Editing a rewound board drops the generations after it.
+    pub fn record_edits(&mut self, board: &Board, rng: &GameRng, tally: &Tally) {
+        let world_matches = board
+            .world()
+            .is_none_or(|world| *world == self.recorded_world);
+        if !self.recorded.matches(board) || !world_matches {
+            self.record_jump(board, rng, tally, 0);
+        }
+    }
+
+    /// Drops the generations after the current one so the simulation can
+    /// carry on from here.
+    pub fn resume(&mut self) {
+        self.latest -= self.rewound_generations();
+        let keep = self.diffs.len() - self.rewound;
+        self.diffs.truncate(keep);
+        self.rewound = 0;
+    }
+
+    /// The dice as they were at the diff the board is at.
+    fn rng_at(&self, rewound: usize) -> Option<&GameRng> {
+        match self.diffs.len() - rewound {
+            0 => self.start_rng.as_ref(),
+            i => Some(&self.diffs[i - 1].rng),
+        }
+    }
+
+    /// What had been counted at the diff the board is at.
+    pub fn tally(&self) -> &Tally {
+        match self.diffs.len() - self.rewound {
+            0 => &self.start_tally,
+            i => &self.diffs[i - 1].tally,
+        }
+    }
+
+    /// Changes what had been counted at the diff the board is at, for counts
+    /// only settled after it was recorded.
+    pub fn amend_tally(&mut self, amend: impl FnOnce(&mut Tally)) {
+        match self.diffs.len() - self.rewound {
+            0 => amend(&mut self.start_tally),
+            i => amend(&mut self.diffs[i - 1].tally),
+        }
+    }
+
+    /// Rewinds `board` and `rng` up to `n` diffs, returning how many it
+    /// went. Edits made since the last diff are recorded first, with
+    /// `tally`, so they aren't lost.
+    pub fn step_back(
+        &mut self,
+        board: &mut Board,
+        rng: &mut GameRng,
+        tally: &Tally,
+        n: usize,
+    ) -> usize {
+        self.record_edits(board, rng, tally);
+        let n = n.min(self.diffs.len() - self.rewound);
+        for _ in 0..n {
+            self.diffs[self.diffs.len() - 1 - self.rewound].undo(board);
+            self.rewound += 1;
+        }
+        self.restore(board, rng);
+        n
+    }
+
+    /// Replays up to `n` rewound diffs, returning how many it went.
+    pub fn step_forward(
+        &mut self,
+        board: &mut Board,
+        rng: &mut GameRng,
+        tally: &Tally,
+        n: usize,
+    ) -> usize {
+        self.record_edits(board, rng, tally);
+        let n = n.min(self.rewound);
+        for _ in 0..n {
+            self.diffs[self.diffs.len() - self.rewound].redo(board);
+            self.rewound -= 1;
+        }
+        self.restore(board, rng);
+        n
+    }
+
+    /// Takes `board` as the recorded one and rolls `rng` back to the dice
+    /// of the diff it is at.
+    fn restore(&mut self, board: &Board, rng: &mut GameRng) {
+        self.recorded = Snapshot::of(board);
+        self.recorded_world = world_of(board);
+        if let Some(recorded) = self.rng_at(self.rewound) {
+            *rng = recorded.clone();
+        }
+    }
+
+    /// Moves `board` to `rewound` generations behind the latest one.
+    pub fn seek(&mut self, board: &mut Board, rng: &mut GameRng, tally: &Tally, rewound: usize) {
+        let rewound = rewound.min(self.diffs.len());
+        if rewound > self.rewound {
+            self.step_back(board, rng, tally, rewound - self.rewound);
+        } else {
+            self.step_forward(board, rng, tally, self.rewound - rewound);
+        }
+    }
+}
+
+/// A copy of the world around `board`, empty if it has none.
+fn world_of(board: &Board) -> SparseWorld {
+    board.world().cloned().unwrap_or_default()
+}
+
+/// The entries that differ between `before` and `after`, as
+/// `(index, before, after)`.
+fn changes<T: Copy + PartialEq>(before: &[T], after: &[T]) -> Vec<(usize, T, T)> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (b, a))| b != a)
+        .map(|(i, (b, a))| (i, *b, *a))
+        .collect()
+}
+
+#[derive(Component)]
+struct TimelineBar;
+
+#[derive(Component)]
+struct TimelineFill;
+
+#[derive(Component)]
+struct TimelineLabel;
+
+/// The counters a [`Tally`] is taken from and rewound into.
+#[derive(SystemParam)]
+pub struct Tallies<'w> {
+    water_spent: ResMut<'w, WaterSpent>,
+    kills: ResMut<'w, Kills>,
+    stability: ResMut<'w, Stability>,
+    territories: ResMut<'w, Territories>,
+    fielded: ResMut<'w, Fielded>,
+    overrun_towers: ResMut<'w, OverrunTowers>,
+    stats: ResMut<'w, CellStats>,
+}
+
+impl Tallies<'_> {
+    pub fn tally(&self) -> Tally {
+        Tally {
+            water_spent: *self.water_spent,
+            kills: self.kills.clone(),
+            stability: self.stability.clone(),
+            territories: self.territories.tally(),
+            fielded: *self.fielded,
+            overrun_towers: self.overrun_towers.clone(),
+        }
+    }
+
+    /// Rewinds the counters to `tally`. The heat maps aren't kept in the
+    /// history, so they start over from here.
+    fn restore(&mut self, tally: &Tally) {
+        *self.water_spent = tally.water_spent;
+        *self.kills = tally.kills.clone();
+        *self.stability = tally.stability.clone();
+        self.territories.restore(&tally.territories);
+        *self.fielded = tally.fielded;
+        self.overrun_towers.set_if_neq(tally.overrun_towers.clone());
+        self.stats.clear();
+    }
+}
+
+fn reset_history(mut history: ResMut<History>, board: Res<Board>, rng: Res<GameRng>) {
+    history.reset(&board, &rng);
+}
+
+fn spawn_timeline(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Timeline"),
+            StateScoped(Screen::Gameplay),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                width: Val::Percent(40.0),
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|timeline| {
+            timeline.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+                TimelineLabel,
+            ));
+            timeline
+                .spawn((
+                    Button,
+                    TimelineBar,
+                    RelativeCursorPosition::default(),
+                    Name::new("TimelineBar"),
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(TIMELINE_COLOR),
+                ))
+                .with_child((
+                    TimelineFill,
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(TIMELINE_FILL_COLOR),
+                    Pickable::IGNORE,
+                ));
+        });
+}
+
+fn update_timeline(
+    history: Res<History>,
+    playing: Res<Playing>,
+    mut label: Query<&mut Text, With<TimelineLabel>>,
+    mut fill: Query<&mut Node, With<TimelineFill>>,
+) {
+    if !history.is_changed() && !playing.is_changed() {
+        return;
+    }
+    let state = if history.is_rewound() {
+        format!("rewound {}", history.rewound())
+    } else if playing.0 {
+        "playing".to_string()
+    } else {
+        "paused".to_string()
+    };
+    for mut text in &mut label {
+        text.0 = format!("Generation {} ({state})", history.generation());
+    }
+    let fraction = if history.is_empty() {
+        1.0
+    } else {
+        1.0 - history.rewound() as f32 / history.len() as f32
+    };
+    for mut node in &mut fill {
+        node.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+/// Space pauses, or resumes from the generation on screen.
+fn toggle_playing(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut playing: ResMut<Playing>,
+    mut history: ResMut<History>,
+) {
+    if !keys.just_pressed(KeyCode::Space) {
+        return;
+    }
+    if playing.0 {
+        playing.0 = false;
+    } else {
+        history.resume();
+        playing.0 = true;
+    }
+}
+
+/// Left/right arrows step back/forward through the history, 10 at a time
+/// with shift held. Stepping forward past the latest generation pauses and
+/// runs new generations one step at a time.
+fn step_through_history(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut playing: ResMut<Playing>,
+    mut pending: ResMut<PendingGenerations>,
+    mut history: ResMut<History>,
+    mut board: ResMut<Board>,
+    mut rng: ResMut<GameRng>,
+    mut tallies: Tallies,
+) {
+    let n = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        BIG_STEP
+    } else {
+        1
+    };
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        history.step_back(&mut board, &mut rng, &tallies.tally(), n);
+        tallies.restore(history.tally());
+    } else if keys.just_pressed(KeyCode::ArrowRight) {
+        if history.is_rewound() {
+            history.step_forward(&mut board, &mut rng, &tallies.tally(), n);
+            tallies.restore(history.tally());
+        } else {
+            pending.0 += n;
+        }
+    } else {
+        return;
+    }
+    playing.0 = false;
+}
+
+/// Clicking or dragging on the timeline bar jumps to that generation.
+fn scrub_timeline(
+    bar: Query<(&Interaction, &RelativeCursorPosition), With<TimelineBar>>,
+    mut playing: ResMut<Playing>,
+    mut history: ResMut<History>,
+    mut board: ResMut<Board>,
+    mut rng: ResMut<GameRng>,
+    mut tallies: Tallies,
+) {
+    for (interaction, cursor) in &bar {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        let len = history.len();
+        let shown = (position.x.clamp(0.0, 1.0) * len as f32).round() as usize;
+        let rewound = len - shown;
+        if rewound == history.rewound() {
+            continue;
+        }
+        playing.0 = false;
+        history.seek(&mut board, &mut rng, &tallies.tally(), rewound);
+        tallies.restore(history.tally());
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<History>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            (
+                // Towers roll the dice for their spots first.
+                reset_history
+                    .after(populate_player_region)
+                    .after(spawn_towers),
+                spawn_timeline,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (toggle_playing, step_through_history, scrub_timeline)
+                    .chain()
+                    .in_set(AppSystems::RecordInput),
+                update_timeline.in_set(AppSystems::Update),
+            )
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::gol::{cell::CellType, rule::Rules, topology::Topology};
+
+    #[test]
+    fn rewinding_undoes_edits_between_generations() {
+        let mut board = Board::new(8, 8);
+        let mut rng = GameRng::fixed(7);
+        let mut history = History::default();
+        history.reset(&board, &rng);
+
+        // A blinker placed before the first generation, then one stepped.
+        for x in 2..5 {
+            board.set(x, 4, CellState::Alive(CellType::TREE));
+        }
+        let placed = Snapshot::of(&board);
+        history.record_edits(&board, &rng, &Tally::default());
+        board.step(&Rules::default(), &mut rng.rules);
+        history.record(&board, &rng, &Tally::default());
+        // Cleared while paused, with nothing recorded yet.
+        board.clear();
+
+        assert_eq!(
+            history.step_back(&mut board, &mut rng, &Tally::default(), 1),
+            1
+        );
+        assert!(history.is_rewound());
+        assert_eq!(history.generation(), 1);
+        assert_eq!(
+            history.step_back(&mut board, &mut rng, &Tally::default(), 1),
+            1
+        );
+        assert!(placed.matches(&board));
+        assert_eq!(history.generation(), 0);
+        assert_eq!(
+            history.step_back(&mut board, &mut rng, &Tally::default(), 1),
+            1
+        );
+        assert_eq!(board.count(CellType::TREE), 0);
+        assert_eq!(
+            history.step_back(&mut board, &mut rng, &Tally::default(), 1),
+            0
+        );
+
+        history.step_forward(&mut board, &mut rng, &Tally::default(), 3);
+        assert_eq!(board.count(CellType::TREE), 0);
+        assert_eq!(history.generation(), 1);
+    }
+
+    #[test]
+    fn rewinding_restores_the_world_beyond_an_unbounded_grid() {
+        let rules = Rules::default();
+        let mut rng = GameRng::fixed(7);
+        let mut board = Board::new(8, 8).with_topology(Topology::Unbounded);
+        for (x, y) in [(1, 5), (2, 5), (3, 5), (3, 6), (2, 7)] {
+            board.set(x, y, CellState::Alive(CellType::TREE));
+        }
+        let mut history = History::default();
+        history.reset(&board, &rng);
+        let mut expected = board.clone();
+        expected.run(20, &rules, &mut rng.clone().rules);
+
+        // The glider leaves the grid after a few generations.
+        for _ in 0..40 {
+            board.step(&rules, &mut rng.rules);
+            history.record(&board, &rng, &Tally::default());
+        }
+        assert_eq!(board.count(CellType::TREE), 0);
+        history.step_back(&mut board, &mut rng, &Tally::default(), 20);
+
+        let alive = |board: &Board| {
+            let mut alive: Vec<IVec2> =
+                board.world().unwrap().alive().map(|(pos, _)| pos).collect();
+            alive.sort_by_key(|pos| (pos.y, pos.x));
+            alive
+        };
+        assert_eq!(alive(&board), alive(&expected));
+        assert!(board.cells() == expected.cells());
+    }
+
+    #[test]
+    fn rewinding_rolls_the_dice_back() {
+        let mut board = Board::new(4, 4);
+        let mut rng = GameRng::fixed(7);
+        let mut history = History::default();
+        history.reset(&board, &rng);
+
+        board.step(&Rules::default(), &mut rng.rules);
+        history.record(&board, &rng, &Tally::default());
+        let expected: u64 = rng.clone().ai.r#gen();
+        board.step(&Rules::default(), &mut rng.rules);
+        rng.ai.r#gen::<u64>();
+        history.record(&board, &rng, &Tally::default());
+
+        history.step_back(&mut board, &mut rng, &Tally::default(), 1);
+        history.resume();
+        assert_eq!(rng.ai.r#gen::<u64>(), expected);
+    }
+
+    #[test]
+    fn rewinding_rewinds_the_tally() {
+        let mut board = Board::new(6, 6);
+        for (x, y) in [(2, 2), (3, 2), (2, 3), (3, 3)] {
+            board.set(x, y, CellState::Alive(CellType::TREE));
+        }
+        let mut rng = GameRng::fixed(7);
+        let mut history = History::default();
+        history.reset(&board, &rng);
+
+        // A block is a still life, stable from its second generation on.
+        let mut tally = Tally::default();
+        for _ in 0..5 {
+            board.step(&Rules::default(), &mut rng.rules);
+            tally.stability.record(board.cells());
+            history.record(&board, &rng, &tally);
+        }
+        assert_eq!(history.tally().stability.stable_for(), 4);
+        // Water placed while paused is charged with the edit.
+        board.set(0, 0, CellState::Alive(CellType::WATER));
+        tally.water_spent.0 += 1;
+        history.step_back(&mut board, &mut rng, &tally, 1);
+        assert_eq!(history.tally().water_spent.0, 0);
+        history.step_forward(&mut board, &mut rng, &tally, 1);
+        assert_eq!(history.tally().water_spent.0, 1);
+        assert_eq!(history.tally().stability.stable_for(), 4);
+
+        history.step_back(&mut board, &mut rng, &tally, 3);
+        assert_eq!(history.tally().stability.stable_for(), 2);
+        history.step_back(&mut board, &mut rng, &tally, 3);
+        assert_eq!(history.tally().stability.period(), None);
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy_enhanced_input::prelude::*;
 
+use crate::gol::grid::GridConfig;
 use crate::gol::pattern::{Dir, SavedPatterns};
 use crate::gol::ui::{PatternButton, SellectedPatternButton};
 use crate::screens::Screen;
@@ -63,6 +64,7 @@ fn apply_aim(
     trigger: Trigger<Fired<Aim>>,
     query: Query<&PatternButton, With<SellectedPatternButton>>,
     mut saved: ResMut<SavedPatterns>,
+    config: Res<GridConfig>,
 ) {
     info!("aim: triggered");
 
@@ -79,7 +81,7 @@ fn apply_aim(
         };
         let dir: Dir = vec2.into();
         info!("apply_aim: dir = {:?}", dir);
-        pattern.change_heading(dir);
+        pattern.change_heading(dir, config.neighbourhood);
         pattern.name.clone()
     };
     saved.set_changed();
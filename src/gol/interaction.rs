@@ -1,16 +1,81 @@
 use super::{
-    cell::{Cell, CellState},
-    grid::CELL_SIZE,
+    board::Board,
+    cell::CellState,
+    grid::GridConfig,
     pattern::{SavedPatterns, SelectedPattern},
 };
-use crate::gol::{cell::CellType, pattern::Pattern, patterns_io::load_patterns};
+use crate::gol::{
+    self,
+    cell::{CellType, Owner},
+    level::is_painting,
+    pattern::Pattern,
+    patterns_io::load_patterns,
+    score::WaterSpent,
+};
 use crate::screens::Screen;
 use bevy::prelude::*;
+use std::fmt;
 
 #[derive(Resource, Default)]
 struct DragStart(Option<(Vec2, f64)>); // Store start position and time of drag
 
-const CELL_STATE_PLAYER_CLICK: CellState = CellState::Alive(CellType::Water);
+const CELL_STATE_PLAYER_CLICK: CellState = CellState::Alive(CellType::WATER);
+
+/// How a placed pattern combines with the cells already on the board.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlacementMode {
+    /// The pattern's bounding box replaces what was there.
+    Overwrite,
+    /// Live pattern cells are set, everything else is left alone.
+    Union,
+    /// Live pattern cells flip the cells under them.
+    #[default]
+    Xor,
+    /// Live pattern cells clear the cells under them.
+    Erase,
+    /// Live pattern cells only land on empty cells, not decaying ones.
+    OnlyOnEmpty,
+}
+
+impl PlacementMode {
+    pub const ALL: [PlacementMode; 5] = [
+        PlacementMode::Overwrite,
+        PlacementMode::Union,
+        PlacementMode::Xor,
+        PlacementMode::Erase,
+        PlacementMode::OnlyOnEmpty,
+    ];
+
+    /// The next mode in [`PlacementMode::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+
+    /// The new state of a `current` cell under a live pattern cell, or
+    /// `None` to leave it as it is.
+    pub fn apply(self, current: CellState, state_alive: CellState) -> Option<CellState> {
+        match self {
+            PlacementMode::Overwrite | PlacementMode::Union => Some(state_alive),
+            PlacementMode::Xor if current.is_alive() => Some(CellState::Dead),
+            PlacementMode::Xor => Some(state_alive),
+            PlacementMode::Erase => Some(CellState::Dead),
+            PlacementMode::OnlyOnEmpty => current.is_dead().then_some(state_alive),
+        }
+    }
+}
+
+impl fmt::Display for PlacementMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PlacementMode::Overwrite => "Overwrite",
+            PlacementMode::Union => "Union",
+            PlacementMode::Xor => "Toggle",
+            PlacementMode::Erase => "Erase",
+            PlacementMode::OnlyOnEmpty => "Empty only",
+        };
+        write!(f, "{}", s)
+    }
+}
 
 fn drag_start(
     buttons: Res<ButtonInput<MouseButton>>,
@@ -43,9 +108,12 @@ fn drag_end_or_click(
     camera_q: Query<(&Camera, &GlobalTransform)>,
     time: Res<Time<Fixed>>,
     drag_start: Res<DragStart>,
-    mut cells: Query<(&mut Sprite, &mut Cell, &Transform)>,
+    mut board: ResMut<Board>,
     selected: Res<SelectedPattern>,
     mut saved: ResMut<SavedPatterns>,
+    config: Res<GridConfig>,
+    mode: Res<PlacementMode>,
+    mut spent: ResMut<WaterSpent>,
 ) {
     if !buttons.just_released(MouseButton::Left) {
         return;
@@ -69,42 +137,50 @@ fn drag_end_or_click(
 
     let duration = time.elapsed_secs_f64() - start_time;
     let pattern_name = &selected.0;
-    if duration < 1.0 && start_pos.distance(end) < CELL_SIZE {
-        if selected.0 == "1x1" {
-            // If the selected pattern is "1x1", toggle the cell at the clicked position
-            toggle_cell(&mut cells, end, CELL_STATE_PLAYER_CLICK, CellState::Dead);
-        } else {
-            // Otherwise, place pattern
-            let Some(pattern) = find_pattern(saved.as_ref(), pattern_name) else {
-                return;
-            };
-            place_pattern(
-                &mut cells,
-                pattern,
-                end,
-                CELL_STATE_PLAYER_CLICK,
-                CellState::Dead,
-            );
-        }
+    if duration < 1.0 && start_pos.distance(end) < config.cell_size {
+        spend_water(&mut board, &mut spent, |board| {
+            if selected.0 == "1x1" {
+                // If the selected pattern is "1x1", place a single cell at the clicked position
+                place_cell(
+                    board,
+                    &config,
+                    end,
+                    CELL_STATE_PLAYER_CLICK,
+                    Owner::PLAYER,
+                    *mode,
+                );
+            } else {
+                // Otherwise, place pattern
+                let Some(pattern) = find_pattern(saved.as_ref(), pattern_name) else {
+                    return;
+                };
+                place_pattern(
+                    board,
+                    &config,
+                    pattern,
+                    end,
+                    CELL_STATE_PLAYER_CLICK,
+                    Owner::PLAYER,
+                    *mode,
+                );
+            }
+        });
         return;
     }
 
     let min = start_pos.min(end);
     let max = start_pos.max(end);
 
+    // Only visit the cells under the dragged rectangle.
     let mut selected = vec![];
-    for (_, cell, trans) in cells {
-        let world_pos = trans.translation.truncate();
-        if world_pos.x >= min.x
-            && world_pos.x <= max.x
-            && world_pos.y >= min.y
-            && world_pos.y <= max.y
-            && cell.state.is_alive()
-        {
-            let rel_x = (world_pos.x - min.x).round() as i32 / CELL_SIZE as i32;
-            let rel_y = (world_pos.y - min.y).round() as i32 / CELL_SIZE as i32;
-            selected.push((rel_x, rel_y));
+    for (x, y) in config.world_rect_to_grid(min, max) {
+        if !board.get(x, y).is_alive() {
+            continue;
         }
+        let world_pos = config.grid_to_world(x, y);
+        let rel_x = (world_pos.x - min.x).round() as i32 / config.cell_size as i32;
+        let rel_y = (world_pos.y - min.y).round() as i32 / config.cell_size as i32;
+        selected.push((rel_x, rel_y));
     }
 
     // For now, use a placeholder name; later prompt via UI
@@ -117,6 +193,15 @@ fn drag_end_or_click(
     println!("Saved pattern '{name}': {:?}", selected);
 }
 
+/// Runs `place` on the board and charges the player for the water cells it
+/// added.
+fn spend_water(board: &mut Board, spent: &mut WaterSpent, place: impl FnOnce(&mut Board)) {
+    let water = |board: &Board| board.count_owned_kind(Owner::PLAYER, CellType::WATER);
+    let before = water(board);
+    place(board);
+    spent.0 += water(board).saturating_sub(before) as u32;
+}
+
 pub fn find_pattern<'a>(patterns: &'a SavedPatterns, pattern_name: &str) -> Option<&'a Pattern> {
     let result = patterns.0.get(pattern_name);
     if result.is_none() {
@@ -126,16 +211,48 @@ pub fn find_pattern<'a>(patterns: &'a SavedPatterns, pattern_name: &str) -> Opti
 }
 
 pub fn place_pattern(
-    cells: &mut Query<(&mut Sprite, &mut Cell, &Transform)>,
+    board: &mut Board,
+    config: &GridConfig,
     pattern: &Pattern,
     world_pos: Vec2,
     state_alive: CellState,
-    state_dead: CellState,
+    owner: Owner,
+    mode: PlacementMode,
 ) {
-    info!("Placing pattern '{}' at {world_pos:?}", pattern.name);
+    info!(
+        "Placing pattern '{}' at {world_pos:?} ({mode})",
+        pattern.name
+    );
+    let origin = config.world_to_grid_unbounded(world_pos);
+    if mode == PlacementMode::Overwrite {
+        // Clear the pattern's bounding box first so its dead cells count too.
+        let min = pattern
+            .cells
+            .iter()
+            .fold(IVec2::MAX, |m, &(x, y)| m.min(IVec2::new(x, y)));
+        let max = pattern
+            .cells
+            .iter()
+            .fold(IVec2::MIN, |m, &(x, y)| m.max(IVec2::new(x, y)));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let pos = config.neighbourhood.translate(origin, IVec2::new(x, y));
+                if let Some((x, y)) = config.place(pos)
+                    && board.terrain(x, y).holds_cells()
+                {
+                    board.set(x, y, CellState::Dead);
+                    board.set_owner(x, y, Owner::NONE);
+                }
+            }
+        }
+    }
     for (x, y) in pattern.cells.iter() {
-        let cell_pos = world_pos + Vec2::new(*x as f32 * CELL_SIZE, *y as f32 * CELL_SIZE);
-        toggle_cell(cells, cell_pos, state_alive, state_dead);
+        // Cells past the edge wrap around or are dropped, depending on the topology.
+        let pos = config.neighbourhood.translate(origin, IVec2::new(*x, *y));
+        let Some((x, y)) = config.place(pos) else {
+            continue;
+        };
+        place_cell_at(board, x, y, state_alive, owner, mode);
     }
 }
 
@@ -143,7 +260,9 @@ fn click_to_toggle_cell(
     windows: Query<&Window>,
     buttons: Res<ButtonInput<MouseButton>>,
     camera_q: Single<(&Camera, &GlobalTransform)>,
-    mut cells: Query<(&mut Sprite, &mut Cell, &Transform)>,
+    mut board: ResMut<Board>,
+    config: Res<GridConfig>,
+    mut spent: ResMut<WaterSpent>,
 ) {
     if !buttons.just_pressed(MouseButton::Left) {
         return;
@@ -160,44 +279,133 @@ fn click_to_toggle_cell(
     let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else {
         return;
     };
-    toggle_cell(
-        &mut cells,
-        world_pos,
-        CELL_STATE_PLAYER_CLICK,
-        CellState::Dead,
-    );
+    spend_water(&mut board, &mut spent, |board| {
+        place_cell(
+            board,
+            &config,
+            world_pos,
+            CELL_STATE_PLAYER_CLICK,
+            Owner::PLAYER,
+            PlacementMode::Xor,
+        );
+    });
 }
 
-fn toggle_cell(
-    cells: &mut Query<(&mut Sprite, &mut Cell, &Transform)>,
+fn place_cell(
+    board: &mut Board,
+    config: &GridConfig,
     world_pos: Vec2,
     state_alive: CellState,
-    state_dead: CellState,
+    owner: Owner,
+    mode: PlacementMode,
 ) {
-    for (mut sprite, mut cell, transform) in cells.iter_mut() {
-        let cell_pos = transform.translation.truncate();
-        let half_size = CELL_SIZE / 2.0;
-        let in_x = (world_pos.x - cell_pos.x).abs() < half_size;
-        let in_y = (world_pos.y - cell_pos.y).abs() < half_size;
-
-        if in_x && in_y {
-            cell.state = if cell.state.is_alive() {
-                state_dead
-            } else {
-                state_alive
-            };
+    let Some((x, y)) = config.world_to_grid(world_pos) else {
+        return;
+    };
+    place_cell_at(board, x, y, state_alive, owner, mode);
+}
 
-            sprite.color = cell.state.color();
-            break;
-        }
+/// Places a cell for `owner`; cells it clears belong to nobody. Nothing is
+/// placed where the terrain can't hold cells.
+fn place_cell_at(
+    board: &mut Board,
+    x: usize,
+    y: usize,
+    state_alive: CellState,
+    owner: Owner,
+    mode: PlacementMode,
+) {
+    if !board.terrain(x, y).holds_cells() {
+        return;
+    }
+    if let Some(state) = mode.apply(board.get(x, y), state_alive) {
+        board.set(x, y, state);
+        board.set_owner(x, y, if state.is_alive() { owner } else { Owner::NONE });
     }
 }
 
 pub(super) fn plugin(app: &mut App) {
     app //.add_systems(Update, click_to_toggle_cell)
-        .add_systems(Update, (drag_start, drag_end_or_click))
+        .add_systems(
+            Update,
+            (drag_start, drag_end_or_click).run_if(not(is_painting)),
+        )
         .add_systems(OnEnter(Screen::Splash), load_patterns)
         .insert_resource(DragStart::default())
+        .init_resource::<PlacementMode>()
         .insert_resource(SelectedPattern("1x1".to_string()))
         .insert_resource(SavedPatterns::default());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gol::terrain::Terrain;
+
+    #[test]
+    fn modes_apply_to_dead_alive_and_decaying_cells() {
+        let water = CellState::Alive(CellType::WATER);
+        let dead = CellState::Dead;
+        let currents = [
+            dead,
+            CellState::Alive(CellType::FIRE),
+            CellState::Decaying(CellType::FIRE, 0),
+        ];
+        let table = [
+            (
+                PlacementMode::Overwrite,
+                [Some(water), Some(water), Some(water)],
+            ),
+            (
+                PlacementMode::Union,
+                [Some(water), Some(water), Some(water)],
+            ),
+            (PlacementMode::Xor, [Some(water), Some(dead), Some(water)]),
+            (PlacementMode::Erase, [Some(dead), Some(dead), Some(dead)]),
+            (PlacementMode::OnlyOnEmpty, [Some(water), None, None]),
+        ];
+        for (mode, expected) in table {
+            for (current, expected) in currents.into_iter().zip(expected) {
+                assert!(mode.apply(current, water) == expected, "{mode}");
+            }
+        }
+    }
+
+    #[test]
+    fn overwrite_clears_the_bounding_box_but_not_walls() {
+        let config = GridConfig::new(6, 6);
+        let mut board = Board::new(6, 6);
+        for y in 0..6 {
+            for x in 0..6 {
+                board.set(x, y, CellState::Alive(CellType::FIRE));
+                board.set_owner(x, y, Owner::AI);
+            }
+        }
+        board.set_terrain(2, 1, Terrain::Wall);
+        board.set_terrain(3, 3, Terrain::Wall);
+        // Corners of a 3x3 box, the top right one on a wall.
+        let pattern = Pattern::new_deleatable("corners", vec![(0, 0), (2, 2)]);
+
+        place_pattern(
+            &mut board,
+            &config,
+            &pattern,
+            config.grid_to_world(1, 1),
+            CellState::Alive(CellType::WATER),
+            Owner::PLAYER,
+            PlacementMode::Overwrite,
+        );
+        for (x, y, state) in board.iter() {
+            let in_box = (1..=3).contains(&x) && (1..=3).contains(&y);
+            let expected = match (x, y) {
+                (1, 1) => CellState::Alive(CellType::WATER),
+                _ if in_box => CellState::Dead,
+                _ => CellState::Alive(CellType::FIRE),
+            };
+            assert!(state == expected, "({x}, {y})");
+        }
+        assert_eq!(board.owner(1, 1), Owner::PLAYER);
+        assert_eq!(board.owner(2, 2), Owner::NONE);
+        assert_eq!(board.terrain(3, 3), Terrain::Wall);
+    }
+}
@@ -0,0 +1,369 @@
+//! Terrain maps, loaded from `.level.txt` assets and drawn in the map
+//! editor.
+//!
+//! A level file is a picture of the map, top row first, with one
+//! [`Terrain::symbol`] per cell:
+//!
+//! ```text
+//! ; A wall across the middle with a gap.
+//! name: Chokepoint
+//! ................
+//! ######....######
+//! ................
+//! ```
+//!
+//! Lines starting with `;` are comments and short rows are padded with open
+//! ground. Maps are stretched to the size of the grid. `win:` lines set the
+//! [`WinCondition`]s of matches on the level, in place of the game mode's.
+
+use std::{fmt, io};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+
+use crate::{
+    asset_tracking::LoadResource,
+    gol::{
+        self,
+        board::Board,
+        grid::GridConfig,
+        patterns_io,
+        terrain::Terrain,
+        win::{WinCondition, WinConditionParseError},
+    },
+    screens::Screen,
+};
+
+/// The levels offered by the settings menu, under `assets/levels`.
+const LEVELS: &[&str] = &["lanes", "chokepoint", "garden"];
+
+/// Name of the map saved from the editor.
+pub const CUSTOM_LEVEL: &str = "Custom";
+
+/// A map of terrain.
+#[derive(Asset, TypePath, Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: String,
+    width: usize,
+    height: usize,
+    /// Row-major from the bottom row, like the board.
+    terrain: Vec<Terrain>,
+    /// How matches on this level end; empty to use the game mode's.
+    pub win: Vec<WinCondition>,
+}
+
+impl Default for Level {
+    /// Open ground everywhere.
+    fn default() -> Self {
+        Self {
+            name: "Open".to_string(),
+            width: 0,
+            height: 0,
+            terrain: Vec::new(),
+            win: Vec::new(),
+        }
+    }
+}
+
+impl Level {
+    /// The terrain of `board` as a level.
+    pub fn from_board(name: &str, board: &Board) -> Self {
+        let (width, height) = (board.width(), board.height());
+        Self {
+            name: name.to_string(),
+            width,
+            height,
+            terrain: (0..width * height)
+                .map(|i| board.terrain(i % width, i / width))
+                .collect(),
+            win: Vec::new(),
+        }
+    }
+
+    /// The map stretched to a `width` by `height` grid, row-major from the
+    /// bottom row.
+    pub fn terrain_for(&self, width: usize, height: usize) -> Vec<Terrain> {
+        if self.terrain.is_empty() {
+            return vec![Terrain::Open; width * height];
+        }
+        (0..width * height)
+            .map(|i| {
+                let x = i % width * self.width / width;
+                let y = i / width * self.height / height;
+                self.terrain[y * self.width + x]
+            })
+            .collect()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        let mut name = None;
+        let mut win = Vec::new();
+        let mut rows = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("name:") {
+                name = Some(value.trim().to_string());
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("win:") {
+                let condition = value
+                    .parse()
+                    .map_err(|err| LevelError::Win(err, number + 1))?;
+                win.push(condition);
+                continue;
+            }
+            let row = line
+                .chars()
+                .map(|symbol| {
+                    Terrain::from_symbol(symbol).ok_or(LevelError::Symbol(symbol, number + 1))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return Err(LevelError::Empty);
+        }
+        let height = rows.len();
+        let mut terrain = Vec::with_capacity(width * height);
+        // Files start with the top row.
+        for mut row in rows.into_iter().rev() {
+            row.resize(width, Terrain::Open);
+            terrain.extend(row);
+        }
+        Ok(Self {
+            name: name.unwrap_or_else(|| "Unnamed".to_string()),
+            width,
+            height,
+            terrain,
+            win,
+        })
+    }
+
+    /// The level in the format [`Level::parse`] reads.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("name: {}\n", self.name);
+        for condition in &self.win {
+            text += &format!("win: {condition}\n");
+        }
+        for row in self.terrain.chunks(self.width.max(1)).rev() {
+            text.extend(row.iter().map(|terrain| terrain.symbol()));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reads the level file given by `--level PATH` (or `--level=PATH`) on
+    /// the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let path = if arg == "--level" {
+                args.next()?
+            } else if let Some(value) = arg.strip_prefix("--level=") {
+                value.to_string()
+            } else {
+                continue;
+            };
+            let level = std::fs::read_to_string(&path)
+                .map_err(LevelError::Io)
+                .and_then(|text| Level::parse(&text));
+            return match level {
+                Ok(level) => Some(level),
+                Err(err) => {
+                    warn!("Ignoring level '{path}': {err}");
+                    None
+                }
+            };
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    /// The file has no map rows.
+    Empty,
+    /// A character that isn't a [`Terrain::symbol`], with its line.
+    Symbol(char, usize),
+    /// A `win:` line that isn't a win condition, with its line.
+    Win(WinConditionParseError, usize),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "could not read level: {err}"),
+            LevelError::Empty => write!(f, "level has no map"),
+            LevelError::Symbol(symbol, line) => {
+                write!(f, "unknown terrain '{symbol}' on line {line}")
+            }
+            LevelError::Win(err, line) => write!(f, "{err} on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, LevelError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Level::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.txt"]
+    }
+}
+
+/// Every level of [`LEVELS`].
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LevelAssets {
+    #[dependency]
+    levels: Vec<Handle<Level>>,
+}
+
+impl FromWorld for LevelAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            levels: LEVELS
+                .iter()
+                .map(|name| assets.load(format!("levels/{name}.level.txt")))
+                .collect(),
+        }
+    }
+}
+
+impl LevelAssets {
+    /// The level after the one named `current`, wrapping around. Open
+    /// ground comes first and the map saved from the editor, if any, last.
+    pub fn cycle(&self, current: &str, step: i32, assets: &Assets<Level>) -> Level {
+        let mut levels = vec![Level::default()];
+        levels.extend(self.levels.iter().filter_map(|h| assets.get(h)).cloned());
+        levels.extend(load_custom_level());
+        let len = levels.len() as i32;
+        let position = levels.iter().position(|level| level.name == current);
+        let next = position.map_or(0, |i| (i as i32 + step).rem_euclid(len));
+        levels.swap_remove(next as usize)
+    }
+}
+
+/// The terrain the map editor paints, or `None` when it is off and clicks
+/// place patterns.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TerrainBrush(pub Option<Terrain>);
+
+impl TerrainBrush {
+    /// Off, then every terrain in [`Terrain::ALL`] order, wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        let brushes: Vec<Option<Terrain>> = std::iter::once(None)
+            .chain(Terrain::ALL.map(Some))
+            .collect();
+        Self(gol::cycle(&brushes, self.0, step))
+    }
+}
+
+impl fmt::Display for TerrainBrush {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(terrain) => write!(f, "{terrain}"),
+            None => write!(f, "Off"),
+        }
+    }
+}
+
+pub fn is_painting(brush: Res<TerrainBrush>) -> bool {
+    brush.0.is_some()
+}
+
+/// Paints the cells under the cursor while the left button is held.
+fn paint_terrain(
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    brush: Res<TerrainBrush>,
+    config: Res<GridConfig>,
+    mut board: ResMut<Board>,
+) {
+    let Some(terrain) = brush.0 else {
+        return;
+    };
+    if !buttons.pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok((camera, transform)) = camera_q.single() else {
+        return;
+    };
+    let Ok(win) = windows.single() else {
+        return;
+    };
+    let Some(pos) = win.cursor_position() else {
+        return;
+    };
+    let Ok(world) = camera.viewport_to_world_2d(transform, pos) else {
+        return;
+    };
+    let Some((x, y)) = config.world_to_grid(world) else {
+        return;
+    };
+    if board.terrain(x, y) != terrain {
+        board.set_terrain(x, y, terrain);
+    }
+}
+
+/// Saves the board's terrain as the custom level and makes it the level of
+/// the next match.
+pub fn save_custom_level(board: &Board, config: &mut GridConfig) {
+    let level = Level::from_board(CUSTOM_LEVEL, board);
+    patterns_io::save_level(&level.to_text());
+    info!("Saved map '{}'", level.name);
+    config.level = level;
+}
+
+fn load_custom_level() -> Option<Level> {
+    Level::parse(&patterns_io::load_level()?).ok()
+}
+
+fn reset_brush(mut brush: ResMut<TerrainBrush>) {
+    brush.0 = None;
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Level>()
+        .init_asset_loader::<LevelLoader>()
+        .register_type::<LevelAssets>()
+        .load_resource::<LevelAssets>()
+        .init_resource::<TerrainBrush>()
+        .add_systems(OnExit(Screen::Gameplay), reset_brush)
+        .add_systems(
+            Update,
+            paint_terrain.run_if(in_state(Screen::Gameplay).and(is_painting)),
+        );
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::gol;
+
+/// Largest radius of [`Neighbourhood::Moore`], keeping neighbour counts
+/// within what a [`Rule`](crate::gol::rule::Rule) can hold.
+pub const MAX_RADIUS: u8 = 3;
+
+/// Which cells around a cell count as its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    /// Every cell within the given distance along both axes. Radius 1 is
+    /// the usual 3x3 block, larger radii give Larger-than-Life.
+    Moore(u8),
+    /// The four cells sharing an edge.
+    VonNeumann,
+    /// The six cells around a hexagon. Hexagons are laid out in rows with
+    /// every odd row shifted half a cell to the right, so wrapping edges
+    /// only line up on grids with an even height.
+    Hexagonal,
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Self {
+        Neighbourhood::Moore(1)
+    }
+}
+
+impl Neighbourhood {
+    pub const ALL: [Neighbourhood; 5] = [
+        Neighbourhood::Moore(1),
+        Neighbourhood::VonNeumann,
+        Neighbourhood::Hexagonal,
+        Neighbourhood::Moore(2),
+        Neighbourhood::Moore(3),
+    ];
+
+    /// The next neighbourhood in [`Neighbourhood::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+
+    pub fn is_hexagonal(self) -> bool {
+        self == Neighbourhood::Hexagonal
+    }
+
+    /// Offsets of the neighbours of a cell in an even or odd row, row by
+    /// row from the bottom. Only hexagonal grids care about the row.
+    pub fn offsets(self, odd_row: bool) -> Vec<IVec2> {
+        let square = |r: i32, keep: &dyn Fn(i32, i32) -> bool| {
+            let mut offsets = Vec::new();
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    if (dx, dy) != (0, 0) && keep(dx, dy) {
+                        offsets.push(IVec2::new(dx, dy));
+                    }
+                }
+            }
+            offsets
+        };
+        match self {
+            Neighbourhood::Moore(radius) => {
+                square(radius.clamp(1, MAX_RADIUS) as i32, &|_, _| true)
+            }
+            Neighbourhood::VonNeumann => square(1, &|dx, dy| dx == 0 || dy == 0),
+            // Rows above and below cover the cell itself and the one towards
+            // the side the row is shifted to.
+            Neighbourhood::Hexagonal => {
+                let shift = if odd_row { 1 } else { -1 };
+                square(1, &|dx, dy| dy == 0 || dx == 0 || dx == shift)
+            }
+        }
+    }
+
+    /// How far a neighbour can be along either axis.
+    pub fn reach(self) -> i32 {
+        match self {
+            Neighbourhood::Moore(radius) => radius.clamp(1, MAX_RADIUS) as i32,
+            Neighbourhood::VonNeumann | Neighbourhood::Hexagonal => 1,
+        }
+    }
+
+    /// Where the cell at `offset` from `origin` is. On hexagonal grids the
+    /// offset is taken as if `origin` were in an even row, so shapes keep
+    /// their form whichever row they start on.
+    pub fn translate(self, origin: IVec2, offset: IVec2) -> IVec2 {
+        if !self.is_hexagonal() {
+            return origin + offset;
+        }
+        from_axial(to_axial(origin) + to_axial(offset))
+    }
+}
+
+/// Axial hex coordinates of a cell in the row-shifted layout.
+pub fn to_axial(pos: IVec2) -> IVec2 {
+    IVec2::new(pos.x - (pos.y - (pos.y & 1)) / 2, pos.y)
+}
+
+/// Row-shifted position of axial hex coordinates.
+pub fn from_axial(axial: IVec2) -> IVec2 {
+    IVec2::new(axial.x + (axial.y - (axial.y & 1)) / 2, axial.y)
+}
+
+/// Turns the cell at `pos` by `steps` sixths of a turn counter-clockwise
+/// around the origin of a hexagonal grid, with rows going up.
+pub fn rotate_hex(pos: IVec2, steps: i32) -> IVec2 {
+    let axial = to_axial(pos);
+    // Cube coordinates, which rotate by shuffling and negating.
+    let (mut x, mut y, mut z) = (axial.x, -axial.x - axial.y, axial.y);
+    for _ in 0..steps.rem_euclid(6) {
+        (x, y, z) = (-z, -x, -y);
+    }
+    debug_assert_eq!(x + y + z, 0);
+    from_axial(IVec2::new(x, z))
+}
+
+impl fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Neighbourhood::Moore(1) => write!(f, "Moore"),
+            Neighbourhood::Moore(radius) => write!(f, "Moore r{radius}"),
+            Neighbourhood::VonNeumann => write!(f, "von Neumann"),
+            Neighbourhood::Hexagonal => write!(f, "Hexagonal"),
+        }
+    }
+}
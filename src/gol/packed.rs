@@ -0,0 +1,383 @@
+//! Bit-packed, multi-threaded stepping of [`BattleRule::TopTwo`].
+//!
+//! Every live kind gets its own plane with one bit per cell, 64 cells to a
+//! word, plus a one cell halo filled in through the board's [`Topology`].
+//! Neighbour counts are summed word-parallel into bit-sliced counters, so a
+//! whole word of cells is tested against the birth/survival rules at once.
+//!
+//! Only cells on open ground that see a single kind around them (the vast
+//! majority) are decided that way. Cells where kinds meet need the battle
+//! and its coin flips, and cells on other [`Terrain`] their own rules, so
+//! they go through [`Board::next_top_two`] like the scalar step.
+//! Rows are split into bands that run in parallel on the compute task pool.
+//!
+//! [`BattleRule::TopTwo`]: crate::gol::rule::BattleRule::TopTwo
+//! [`Terrain`]: crate::gol::terrain::Terrain
+//! [`Topology`]: crate::gol::topology::Topology
+
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+
+use crate::gol::{
+    board::Board,
+    cell::{CellState, CellType},
+    elements::MAX_ELEMENTS,
+    rule::Rules,
+    terrain::Terrain,
+};
+
+/// Bands per thread, so threads that finish early can pick up more work.
+const BANDS_PER_THREAD: usize = 4;
+
+/// Advances `board` one generation under [`BattleRule::TopTwo`], drawing
+/// the coin flips of contested cells from `seed`.
+///
+/// [`BattleRule::TopTwo`]: crate::gol::rule::BattleRule::TopTwo
+pub fn step_top_two(board: &mut Board, rules: &Rules, seed: u64) {
+    if board.width() == 0 || board.height() == 0 {
+        return;
+    }
+    let planes = Planes::pack(board);
+    let masks: Vec<KindMasks> = planes
+        .kinds
+        .iter()
+        .map(|&kind| KindMasks::new(rules, kind))
+        .collect();
+
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let bands = (pool.thread_num() * BANDS_PER_THREAD).max(1);
+    let width = board.width();
+    let rows_per_band = board.height().div_ceil(bands);
+
+    board.step_with(|board, next| {
+        pool.scope(|scope| {
+            for (band, out) in next.chunks_mut(rows_per_band * width).enumerate() {
+                let planes = &planes;
+                let masks = &masks;
+                scope.spawn(async move {
+                    for (row, out) in out.chunks_mut(width).enumerate() {
+                        let y = band * rows_per_band + row;
+                        step_row(board, planes, masks, rules, seed, y, out);
+                    }
+                });
+            }
+        });
+    });
+}
+
+/// Fills in row `y` of the next generation.
+fn step_row(
+    board: &Board,
+    planes: &Planes,
+    masks: &[KindMasks],
+    rules: &Rules,
+    seed: u64,
+    y: usize,
+    out: &mut [CellState],
+) {
+    let width = board.width();
+    // Padded rows: row `y` of the board is row `y + 1` of the planes.
+    let py = y + 1;
+    for word in 0..planes.words_per_row {
+        // Live neighbour count of each cell, bit-sliced over four words.
+        let mut count = [0u64; 4];
+        let alive = planes.alive();
+        for (dy, with_centre) in [(0, true), (1, false), (2, true)] {
+            let row = planes.row(alive, py + dy - 1);
+            add(&mut count, west(row, word));
+            add(&mut count, east(row, word));
+            if with_centre {
+                add(&mut count, row[word]);
+            }
+        }
+        let count_is: [u64; 9] = std::array::from_fn(|n| equals(&count, n));
+
+        // Which kinds each cell sees in its 3x3 block, and where more than
+        // one of them meets.
+        let mut seen = 0;
+        let mut contested = 0;
+        let mut next_alive = [0u64; MAX_ELEMENTS];
+        let centre_alive = planes.row(alive, py)[word];
+        for (plane, mask) in masks.iter().enumerate() {
+            let mut touched = 0;
+            for dy in 0..3 {
+                let row = planes.row(plane, py + dy - 1);
+                touched |= west(row, word) | row[word] | east(row, word);
+            }
+            contested |= seen & touched;
+            seen |= touched;
+
+            let centre = planes.row(plane, py)[word];
+            let survive = with_count(mask.survive, &count_is);
+            let birth = with_count(mask.birth, &count_is);
+            next_alive[plane] = (centre & survive) | (!centre_alive & touched & birth);
+        }
+
+        let any_alive = next_alive.iter().fold(0, |cells, next| cells | next);
+        for bit in 0..64 {
+            let column = word * 64 + bit;
+            if column == 0 || column > width {
+                continue;
+            }
+            let x = column - 1;
+            out[x] = if contested >> bit & 1 != 0 || board.terrain(x, y) != Terrain::Open {
+                board.next_top_two(x, y, rules, seed)
+            } else if any_alive >> bit & 1 == 0 {
+                CellState::Dead
+            } else {
+                next_alive
+                    .iter()
+                    .zip(&planes.kinds)
+                    .find(|(next, _)| *next >> bit & 1 != 0)
+                    .map_or(CellState::Dead, |(_, &kind)| CellState::Alive(kind))
+            };
+        }
+    }
+}
+
+/// Live cells of each kind on the board, one bit per cell, with a halo.
+struct Planes {
+    /// The kinds that have a plane, in plane order.
+    kinds: Vec<CellType>,
+    words_per_row: usize,
+    rows: usize,
+    /// Every kind plane followed by the plane of all live cells.
+    words: Vec<u64>,
+}
+
+impl Planes {
+    fn pack(board: &Board) -> Self {
+        let (width, height) = (board.width(), board.height());
+        let words_per_row = (width + 2).div_ceil(64);
+        let rows = height + 2;
+        let plane_len = words_per_row * rows;
+
+        let mut kinds = Vec::new();
+        let mut plane_of = [None; MAX_ELEMENTS];
+        for state in board.cells() {
+            if let CellState::Alive(kind) = state
+                && plane_of[kind.index()].is_none()
+            {
+                plane_of[kind.index()] = Some(kinds.len());
+                kinds.push(*kind);
+            }
+        }
+
+        let mut words = vec![0u64; plane_len * (kinds.len() + 1)];
+        let alive_plane = kinds.len();
+        let mut set = |px: usize, py: usize, state: CellState| {
+            let CellState::Alive(kind) = state else {
+                return;
+            };
+            let Some(plane) = plane_of[kind.index()] else {
+                return;
+            };
+            let offset = py * words_per_row + px / 64;
+            let bit = 1 << (px % 64);
+            words[plane * plane_len + offset] |= bit;
+            words[alive_plane * plane_len + offset] |= bit;
+        };
+        for (i, state) in board.cells().iter().enumerate() {
+            set(i % width + 1, i / width + 1, *state);
+        }
+        // The halo holds whatever the topology maps it to.
+        for py in 0..rows {
+            let columns: Vec<usize> = if py == 0 || py == rows - 1 {
+                (0..width + 2).collect()
+            } else {
+                vec![0, width + 1]
+            };
+            for px in columns {
+                if let Some((x, y)) = board.resolve(px as i32 - 1, py as i32 - 1) {
+                    set(px, py, board.get(x, y));
+                }
+            }
+        }
+
+        Self {
+            kinds,
+            words_per_row,
+            rows,
+            words,
+        }
+    }
+
+    /// The plane of all live cells; kind `k` of [`Planes::kinds`] is plane `k`.
+    fn alive(&self) -> usize {
+        self.kinds.len()
+    }
+
+    fn row(&self, plane: usize, py: usize) -> &[u64] {
+        let start = (plane * self.rows + py) * self.words_per_row;
+        &self.words[start..start + self.words_per_row]
+    }
+}
+
+/// Birth and survival neighbour counts of one kind, as bitmasks.
+struct KindMasks {
+    birth: u16,
+    survive: u16,
+}
+
+impl KindMasks {
+    fn new(rules: &Rules, kind: CellType) -> Self {
+        let rule = rules.for_kind(kind);
+        let mask =
+            |f: &dyn Fn(usize) -> bool| (0..=8).filter(|&n| f(n)).fold(0, |mask, n| mask | 1 << n);
+        Self {
+            // A cell with no live neighbours can't see this kind, so count 0
+            // never gives birth here.
+            birth: mask(&|n| n > 0 && rule.births(n)),
+            survive: mask(&|n| rule.survives(n)),
+        }
+    }
+}
+
+/// Cells whose neighbour count is one of the counts in `mask`, given the
+/// cells with each count from [`equals`].
+fn with_count(mask: u16, equals: &[u64; 9]) -> u64 {
+    (0..9)
+        .filter(|n| mask & (1 << n) != 0)
+        .fold(0, |cells, n| cells | equals[n])
+}
+
+/// Each cell's western neighbour, i.e. the row shifted one column east.
+fn west(row: &[u64], word: usize) -> u64 {
+    let carry = if word > 0 { row[word - 1] >> 63 } else { 0 };
+    (row[word] << 1) | carry
+}
+
+/// Each cell's eastern neighbour, i.e. the row shifted one column west.
+fn east(row: &[u64], word: usize) -> u64 {
+    let carry = row.get(word + 1).map_or(0, |next| next << 63);
+    (row[word] >> 1) | carry
+}
+
+/// Adds one bit per cell to the bit-sliced counters.
+fn add(count: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for slice in count.iter_mut() {
+        let sum = *slice ^ carry;
+        carry &= *slice;
+        *slice = sum;
+    }
+}
+
+/// Cells whose bit-sliced count equals `n`.
+fn equals(count: &[u64; 4], n: usize) -> u64 {
+    count.iter().enumerate().fold(!0, |cells, (i, slice)| {
+        cells & if n >> i & 1 != 0 { *slice } else { !*slice }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::gol::{cell::Owner, topology::Topology};
+
+    use super::*;
+
+    const GENERATIONS: usize = 300;
+
+    /// A `width` by `height` board with about a third of the cells alive as
+    /// one of the three classic elements, owned by either side, and with
+    /// some of every [`Terrain`] if `terrain` is set.
+    fn soup(width: usize, height: usize, terrain: bool, seed: u64) -> Board {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let mut board = Board::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if terrain && rng.gen_bool(0.2) {
+                    board.set_terrain(x, y, Terrain::ALL[rng.gen_range(1..Terrain::ALL.len())]);
+                }
+                if board.terrain(x, y).holds_cells() && rng.gen_bool(0.35) {
+                    let kind =
+                        [CellType::TREE, CellType::WATER, CellType::FIRE][rng.gen_range(0..3)];
+                    board.set(x, y, CellState::Alive(kind));
+                    board.set_owner(x, y, Owner(rng.gen_range(1..=2)));
+                }
+            }
+        }
+        board
+    }
+
+    /// Steps one board with [`Board::step`] and a copy with
+    /// [`Board::step_scalar`] from the same seed, checking they agree on
+    /// every generation.
+    fn assert_matches_scalar(topology: Topology, rules: &Rules, terrain: bool) {
+        // Wider than a word, so cells cross word boundaries.
+        let mut packed = soup(70, 40, terrain, 1).with_topology(topology);
+        let mut scalar = packed.clone();
+        let packed_rng = &mut StdRng::seed_from_u64(2);
+        let scalar_rng = &mut StdRng::seed_from_u64(2);
+        for generation in 1..=GENERATIONS {
+            packed.step(rules, packed_rng);
+            scalar.step_scalar(rules, scalar_rng);
+            assert!(
+                packed.cells() == scalar.cells(),
+                "{topology} cells differ at generation {generation}, per-element \
+                 {}, terrain {terrain}, decay {}",
+                rules.per_element,
+                rules.decay,
+            );
+            assert_eq!(
+                packed.owners(),
+                scalar.owners(),
+                "{topology} owners differ at generation {generation}"
+            );
+        }
+        assert!(
+            packed.cells().iter().any(CellState::is_alive),
+            "{topology} died out, so the comparison proved little"
+        );
+    }
+
+    #[test]
+    fn matches_scalar_step_on_every_topology() {
+        for topology in Topology::ALL {
+            for per_element in [false, true] {
+                let rules = Rules {
+                    per_element,
+                    ..Rules::default()
+                };
+                assert_matches_scalar(topology, &rules, false);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_scalar_step_with_terrain_and_decay() {
+        for topology in Topology::ALL {
+            for per_element in [false, true] {
+                let rules = Rules {
+                    per_element,
+                    decay: true,
+                    ..Rules::default()
+                };
+                assert_matches_scalar(topology, &rules, true);
+            }
+        }
+    }
+
+    /// The kernel's target: 1024x1024 at 60 generations a second. Timing
+    /// depends on the machine, so run it by hand with
+    /// `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn steps_1024_squared_at_60_generations_per_second() {
+        let mut board = soup(1024, 1024, false, 3);
+        let rules = Rules::default();
+        let rng = &mut StdRng::seed_from_u64(4);
+        // Let the soup settle into the mostly uncontested board of a match.
+        board.run(20, &rules, rng);
+        let start = Instant::now();
+        board.run(60, &rules, rng);
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed <= Duration::from_secs(1),
+            "60 generations took {elapsed:?}"
+        );
+    }
+}
@@ -2,7 +2,10 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt};
 
-use crate::gol::grid::Region;
+use crate::gol::{
+    neighbourhood::{Neighbourhood, from_axial, rotate_hex, to_axial},
+    region::Region,
+};
 
 #[derive(Resource)]
 pub struct SavedPatterns(pub HashMap<String, Pattern>);
@@ -46,7 +49,11 @@ impl Pattern {
 }
 
 impl Pattern {
-    pub fn change_heading(&mut self, new_dir: Dir) {
+    pub fn change_heading(&mut self, new_dir: Dir, neighbourhood: Neighbourhood) {
+        if neighbourhood.is_hexagonal() {
+            self.change_heading_hex(new_dir);
+            return;
+        }
         match self.dir {
             Dir::N | Dir::E | Dir::S | Dir::W => match new_dir {
                 Dir::NW | Dir::NE | Dir::SW | Dir::SE => {
@@ -91,24 +98,44 @@ impl Pattern {
         }
     }
 
-    /// Returns the width and height of the pattern in cells.
-    ///
-    /// The pattern's cells are guaranteed to have non-negative
-    /// coordinates, so the dimensions are the maximum x and y values
-    /// plus one.
-    fn dimensions(&self) -> (usize, usize) {
-        let max_x = self.cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
-        let max_y = self.cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
-        ((max_x + 1) as usize, (max_y + 1) as usize)
+    /// Hexagons turn in sixths, so the pattern is turned by the multiple
+    /// of 60° nearest to the angle between the headings.
+    fn change_heading_hex(&mut self, new_dir: Dir) {
+        let (Some(from), Some(to)) = (self.dir.degrees(), new_dir.degrees()) else {
+            return;
+        };
+        let steps = ((to - from) as f32 / 60.0).round() as i32;
+        let rotated: Vec<IVec2> = self
+            .cells
+            .iter()
+            .map(|&(x, y)| rotate_hex(IVec2::new(x, y), steps))
+            .collect();
+        // Moving a hex pattern by an odd number of rows changes its shape,
+        // so the rows are moved along the hex axis instead.
+        let min_y = rotated.iter().map(|cell| cell.y).min().unwrap_or(0);
+        let rotated: Vec<(i32, i32)> = rotated
+            .iter()
+            .map(|&cell| from_axial(to_axial(cell) - IVec2::new(0, min_y)))
+            .map(|cell| (cell.x, cell.y))
+            .collect();
+        self.cells = translate_to_positive_coordinates(&rotated);
+        self.dir = new_dir;
     }
-    pub fn to_region_that_accepts_my_cells(&self, given: &Region) -> Region {
-        let (w, h) = self.dimensions();
-        Region::new(
-            given.bounds.min.x,
-            given.bounds.min.y,
-            given.bounds.max.x - w as f32,
-            given.bounds.max.y - h as f32,
-        )
+
+    /// The cells of `given` the pattern can be placed at, by
+    /// [`place_pattern`](crate::gol::interaction::place_pattern), with all
+    /// of its cells inside `given`.
+    pub fn to_region_that_accepts_my_cells(
+        &self,
+        given: &Region,
+        neighbourhood: Neighbourhood,
+    ) -> Region {
+        Region::from_fn(given.width(), given.height(), |x, y| {
+            let origin = IVec2::new(x as i32, y as i32);
+            self.cells.iter().all(|&(cx, cy)| {
+                given.contains_pos(neighbourhood.translate(origin, IVec2::new(cx, cy)))
+            })
+        })
     }
 }
 
@@ -237,6 +264,23 @@ pub enum Dir {
     NW,
 }
 
+impl Dir {
+    /// Heading in degrees counter-clockwise from east, if it has one.
+    pub fn degrees(&self) -> Option<i32> {
+        match self {
+            Dir::E => Some(0),
+            Dir::NE => Some(45),
+            Dir::N => Some(90),
+            Dir::NW => Some(135),
+            Dir::W => Some(180),
+            Dir::SW => Some(225),
+            Dir::S => Some(270),
+            Dir::SE => Some(315),
+            Dir::None | Dir::Unknown => None,
+        }
+    }
+}
+
 impl From<Vec2> for Dir {
     fn from(vec: Vec2) -> Self {
         if vec.x == 0.0 {
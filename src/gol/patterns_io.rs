@@ -58,3 +58,22 @@ pub fn save_patterns(saved: ResMut<SavedPatterns>) {
     #[cfg(not(target_arch = "wasm32"))]
     save_patterns_to_file(json);
 }
+
+/// The text of the custom level saved by the map editor, if there is one.
+pub fn load_level() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    let text = load_level_from_local_storage();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let text = load_level_from_file();
+
+    text
+}
+
+pub fn save_level(text: &str) {
+    #[cfg(target_arch = "wasm32")]
+    save_level_to_local_storage(text);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    save_level_to_file(text);
+}
@@ -37,3 +37,20 @@ pub fn save_patterns_to_file(json: String) {
         let _ = write!(file, "{}", json);
     }
 }
+
+fn level_file_path() -> PathBuf {
+    let tmp_dir = env::var("TMPDIR").unwrap_or_else(|_| "/tmp".to_string());
+    let mut path = PathBuf::from(tmp_dir);
+    path.push("custom.level.txt");
+    path
+}
+
+pub fn load_level_from_file() -> Option<String> {
+    std::fs::read_to_string(level_file_path()).ok()
+}
+
+pub fn save_level_to_file(text: &str) {
+    if let Err(err) = std::fs::write(level_file_path(), text) {
+        warn!("Failed to save map: {err}");
+    }
+}
@@ -4,6 +4,7 @@ use serde_json::from_str;
 use web_sys::window;
 
 const PATTERNS_KEY: &str = "gol-patterns";
+const LEVEL_KEY: &str = "gol-custom-level";
 
 pub fn load_patterns_from_local_storage() -> std::vec::Vec<Pattern> {
     if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
@@ -28,3 +29,14 @@ pub fn save_patterns_to_local_storage(json: String) {
         let _ = storage.set_item(PATTERNS_KEY, &json);
     }
 }
+
+pub fn load_level_from_local_storage() -> Option<String> {
+    let storage = window().and_then(|w| w.local_storage().ok().flatten())?;
+    storage.get_item(LEVEL_KEY).ok().flatten()
+}
+
+pub fn save_level_to_local_storage(text: &str) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = storage.set_item(LEVEL_KEY, text);
+    }
+}
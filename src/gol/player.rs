@@ -1,73 +1,91 @@
 use crate::{
     AppSystems, PausableSystems,
     gol::{
-        cell::{Cell, CellState, CellType},
-        grid::Region,
-        interaction::{find_pattern, place_pattern},
+        board::Board,
+        cell::{CellState, CellType, Owner, RegionOwner},
+        grid::{GridConfig, game_of_life_step},
+        interaction::{PlacementMode, find_pattern, place_pattern},
         pattern::{Dir, Pattern, SavedPatterns},
+        region::Region,
+        rng::GameRng,
+        speed::GenerationsStepped,
+        stability::Stability,
     },
 };
 use bevy::prelude::*;
 use rand::prelude::*;
 
 pub fn populate_player_region(
-    mut cells: Query<(&mut Sprite, &mut Cell, &Transform)>,
+    mut board: ResMut<Board>,
     saved: Res<SavedPatterns>,
+    config: Res<GridConfig>,
+    mut rng: ResMut<GameRng>,
 ) {
     let Some(pattern_unrotated) = find_pattern(saved.as_ref(), "2x2") else {
         return;
     };
 
-    let player_region: Region = Region::from(Dir::S, None);
+    let player_region = config.region(RegionOwner::Player);
     let dirs = vec![Dir::None];
 
     for _ in 0..5 {
         spawn_pattern_at_random_in_region(
-            &mut cells,
+            &mut board,
+            &config,
             pattern_unrotated,
             &player_region,
             &dirs,
-            CellState::Alive(CellType::Tree),
-            CellState::Dead,
+            CellState::Alive(CellType::TREE),
+            Owner::PLAYER,
+            PlacementMode::OnlyOnEmpty,
+            &mut rng,
         );
     }
 }
 
-#[derive(Resource, Default)]
-pub struct AiGliderTimer(Timer);
+/// Generations between the patterns the AI spawns.
+const AI_SPAWN_GENERATIONS: u64 = 5;
 
 struct PatternConfig {
     name: &'static str,
     dirs: &'static [Dir],
+    mode: PlacementMode,
 }
 
 static AI_PATTERN_CONFIGS: &[PatternConfig] = &[
     PatternConfig {
         name: "glider",
         dirs: &[Dir::SE, Dir::SW],
+        mode: PlacementMode::Overwrite,
     },
     PatternConfig {
         name: "LWSS",
         dirs: &[Dir::S],
+        mode: PlacementMode::Overwrite,
     },
     PatternConfig {
         name: "face",
         dirs: &[Dir::E],
+        mode: PlacementMode::Union,
     },
 ];
 
+/// Spawns one of [`AI_PATTERN_CONFIGS`] in the AI's region every
+/// [`AI_SPAWN_GENERATIONS`] generations, unless the board has settled into
+/// repeating itself, so a stagnant match can end.
 pub fn ai_spawn_pattern_on_timer(
-    mut cells: Query<(&mut Sprite, &mut Cell, &Transform)>,
-    mut timer: ResMut<AiGliderTimer>,
-    time: Res<Time>,
+    mut board: ResMut<Board>,
+    stepped: Res<GenerationsStepped>,
+    stability: Res<Stability>,
     //mut commands: Commands,
     saved: Res<SavedPatterns>,
+    config: Res<GridConfig>,
+    mut rng: ResMut<GameRng>,
 ) {
-    timer.0.tick(time.delta());
-    if !timer.0.just_finished() {
+    if !stepped.crossed(AI_SPAWN_GENERATIONS) || stability.period().is_some() {
         return;
     }
-    let Some(pattern_config) = AI_PATTERN_CONFIGS.choose(&mut rand::thread_rng()) else {
+    let Some(pattern_config) = AI_PATTERN_CONFIGS.choose(&mut rng.ai) else {
         return; // No patterns available
     };
 
@@ -75,49 +93,56 @@ pub fn ai_spawn_pattern_on_timer(
         return;
     };
 
-    let ai_region: Region = Region::from(Dir::N, None);
+    let ai_region = config.region(RegionOwner::AI);
 
     spawn_pattern_at_random_in_region(
-        &mut cells,
+        &mut board,
+        &config,
         pattern_unrotated,
         &ai_region,
         pattern_config.dirs,
-        CellState::Alive(CellType::Fire),
-        CellState::Dead,
+        CellState::Alive(CellType::FIRE),
+        Owner::AI,
+        pattern_config.mode,
+        &mut rng,
     );
 }
 
 fn spawn_pattern_at_random_in_region(
-    cells: &mut Query<(&mut Sprite, &mut Cell, &Transform)>,
+    board: &mut Board,
+    config: &GridConfig,
     pattern_unrotated: &Pattern,
     region: &Region,
     dirs: &[Dir],
     state_alive: CellState,
-    state_dead: CellState,
+    owner: Owner,
+    mode: PlacementMode,
+    rng: &mut GameRng,
 ) {
-    let dir = match dirs.choose(&mut rand::thread_rng()) {
+    let dir = match dirs.choose(&mut rng.ai) {
         Some(dir) => dir.clone(),
         None => Dir::E, // No directions available
     };
     let mut pattern = pattern_unrotated.clone();
-    pattern.change_heading(dir);
+    pattern.change_heading(dir, config.neighbourhood);
 
-    let world_pos = pattern
-        .to_region_that_accepts_my_cells(region)
-        .to_world()
-        .to_random_pos();
+    // Regions too small for the pattern get nothing.
+    let Some((x, y)) = pattern
+        .to_region_that_accepts_my_cells(region, config.neighbourhood)
+        .random_cell(&mut rng.ai)
+    else {
+        return;
+    };
+    let world_pos = config.grid_to_world(x, y);
 
-    place_pattern(cells, &pattern, world_pos, state_alive, state_dead);
+    place_pattern(board, config, &pattern, world_pos, state_alive, owner, mode);
 }
 
 pub(super) fn plugin(app: &mut App) {
-    app.insert_resource(AiGliderTimer(Timer::from_seconds(
-        1.0,
-        TimerMode::Repeating,
-    )))
-    .add_systems(
+    app.add_systems(
         FixedUpdate,
         ai_spawn_pattern_on_timer
+            .after(game_of_life_step)
             .in_set(AppSystems::Update)
             .in_set(PausableSystems),
     );
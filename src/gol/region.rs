@@ -0,0 +1,330 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::gol::{self, cell::RegionOwner, grid::GridConfig, pattern::Dir};
+
+/// A set of cells of the grid, of any shape: bands, corners, polygons or
+/// several disjoint islands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    width: usize,
+    height: usize,
+    /// Row-major from the bottom row, like the board.
+    cells: Vec<bool>,
+}
+
+impl Region {
+    /// No cells of a `width` by `height` grid.
+    pub fn empty(width: usize, height: usize) -> Self {
+        Self::from_fn(width, height, |_, _| false)
+    }
+
+    /// The cells of a `width` by `height` grid for which `f` is true.
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> bool) -> Self {
+        Self {
+            width,
+            height,
+            cells: (0..width * height)
+                .map(|i| f(i % width, i / width))
+                .collect(),
+        }
+    }
+
+    /// The cells whose centres lie inside the polygon with the given
+    /// corners, in grid coordinates where cell `x, y` spans `x..x + 1` and
+    /// `y..y + 1`.
+    pub fn polygon(width: usize, height: usize, corners: &[Vec2]) -> Self {
+        Self::from_fn(width, height, |x, y| {
+            let centre = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            // Even-odd rule: count the edges a ray to the right crosses.
+            let mut inside = false;
+            for (i, &a) in corners.iter().enumerate() {
+                let b = corners[(i + 1) % corners.len()];
+                if (a.y > centre.y) != (b.y > centre.y)
+                    && centre.x < a.x + (centre.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                {
+                    inside = !inside;
+                }
+            }
+            inside
+        })
+    }
+
+    /// The cells within `radius` of `centre`, in grid coordinates.
+    pub fn disc(width: usize, height: usize, centre: Vec2, radius: f32) -> Self {
+        Self::from_fn(width, height, |x, y| {
+            Vec2::new(x as f32 + 0.5, y as f32 + 0.5).distance(centre) <= radius
+        })
+    }
+
+    /// The band along an edge of the grid, or the triangle in a corner,
+    /// `range` cells deep; the whole grid for [`Dir::None`].
+    pub fn from(dir: Dir, range_opt: Option<usize>, config: &GridConfig) -> Self {
+        let (width, height) = (config.width, config.height);
+        let range = range_opt.unwrap_or(match dir {
+            Dir::E | Dir::W => config.region_default_width(),
+            _ => config.region_default_height(),
+        });
+        let (w, h) = (width as f32, height as f32);
+        // Corners get triangles with legs twice the depth of a band. Cells
+        // centred on the long side count, in every corner alike.
+        let leg = 2.0 * range as f32;
+        let corner = |x: f32, y: f32, dx: f32, dy: f32| {
+            Self::from_fn(width, height, |cx, cy| {
+                let centre = Vec2::new(cx as f32 + 0.5, cy as f32 + 0.5);
+                (centre.x - x) * dx + (centre.y - y) * dy <= leg
+            })
+        };
+        match dir {
+            Dir::S => Self::from_fn(width, height, |_, y| y < range),
+            Dir::N => Self::from_fn(width, height, |_, y| y + range >= height),
+            Dir::W => Self::from_fn(width, height, |x, _| x < range),
+            Dir::E => Self::from_fn(width, height, |x, _| x + range >= width),
+            Dir::SW => corner(0.0, 0.0, 1.0, 1.0),
+            Dir::SE => corner(w, 0.0, -1.0, 1.0),
+            Dir::NW => corner(0.0, h, 1.0, -1.0),
+            Dir::NE => corner(w, h, -1.0, -1.0),
+            Dir::None | Dir::Unknown => Self::from_fn(width, height, |_, _| true),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the cell at `x, y` is in the region; false off the grid.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+
+    /// Like [`Region::contains`] for positions that may be off the grid.
+    pub fn contains_pos(&self, pos: IVec2) -> bool {
+        pos.x >= 0 && pos.y >= 0 && self.contains(pos.x as usize, pos.y as usize)
+    }
+
+    /// The cells in either region.
+    pub fn union(mut self, other: &Region) -> Self {
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            *cell |= other.contains(i % self.width, i / self.width);
+        }
+        self
+    }
+
+    /// The cells in this region but not in `other`.
+    pub fn minus(mut self, other: &Region) -> Self {
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            *cell &= !other.contains(i % self.width, i / self.width);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.cells.contains(&true)
+    }
+
+    /// Number of cells in the region.
+    pub fn len(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// Grid coordinates of the cells in the region, bottom row first.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell)
+            .map(move |(i, _)| (i % width, i / width))
+    }
+
+    /// A cell of the region picked uniformly, or `None` if it is empty.
+    pub fn random_cell(&self, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        self.cells().choose(rng)
+    }
+}
+
+/// Where the player's and the AI's regions lie.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RegionLayout {
+    /// The player along the bottom edge, the AI along the top.
+    #[default]
+    Bands,
+    /// The player along the left edge, the AI along the right.
+    Sides,
+    /// Triangles in opposite corners, the player's bottom left.
+    Corners,
+    /// Two islands each, the player's in the bottom half, with open ground
+    /// between them.
+    Islands,
+}
+
+impl RegionLayout {
+    pub const ALL: [RegionLayout; 4] = [
+        RegionLayout::Bands,
+        RegionLayout::Sides,
+        RegionLayout::Corners,
+        RegionLayout::Islands,
+    ];
+
+    /// The next layout in [`RegionLayout::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+
+    /// The region of `owner` on a grid of `config`'s size. Unowned ground
+    /// is whatever neither side's region covers.
+    pub fn region(self, owner: RegionOwner, config: &GridConfig) -> Region {
+        let (dir, opposite) = match self {
+            RegionLayout::Bands => (Dir::S, Dir::N),
+            RegionLayout::Sides => (Dir::W, Dir::E),
+            RegionLayout::Corners => (Dir::SW, Dir::NE),
+            RegionLayout::Islands => return islands(owner, config),
+        };
+        match owner {
+            RegionOwner::Player => Region::from(dir, None, config),
+            RegionOwner::AI => Region::from(opposite, None, config),
+            RegionOwner::None => Region::from(Dir::None, None, config)
+                .minus(&Region::from(dir, None, config))
+                .minus(&Region::from(opposite, None, config)),
+        }
+    }
+}
+
+/// Two discs a side, a quarter of the way in from the left and right edges
+/// and a fifth of the way in from the bottom or top.
+fn islands(owner: RegionOwner, config: &GridConfig) -> Region {
+    let (width, height) = (config.width, config.height);
+    let (w, h) = (width as f32, height as f32);
+    let radius = w.min(h) / 8.0;
+    let side = |y: f32| {
+        Region::disc(width, height, Vec2::new(w / 4.0, y), radius).union(&Region::disc(
+            width,
+            height,
+            Vec2::new(w * 0.75, y),
+            radius,
+        ))
+    };
+    let player = side(h / 5.0);
+    let ai = side(h * 0.8);
+    match owner {
+        RegionOwner::Player => player,
+        RegionOwner::AI => ai,
+        RegionOwner::None => Region::from(Dir::None, None, config)
+            .minus(&player)
+            .minus(&ai),
+    }
+}
+
+impl fmt::Display for RegionLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RegionLayout::Bands => "Bands",
+            RegionLayout::Sides => "Sides",
+            RegionLayout::Corners => "Corners",
+            RegionLayout::Islands => "Islands",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The corners of a five-pointed star of `radius` around `centre`, in
+    /// the order they are joined.
+    fn pentagram(centre: Vec2, radius: f32) -> Vec<Vec2> {
+        (0..5)
+            .map(|i| {
+                let angle =
+                    std::f32::consts::FRAC_PI_2 + (i * 2) as f32 * std::f32::consts::TAU / 5.0;
+                centre + radius * Vec2::from_angle(angle)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn polygon_uses_the_even_odd_rule() {
+        let square = [
+            Vec2::new(2.0, 2.0),
+            Vec2::new(6.0, 2.0),
+            Vec2::new(6.0, 6.0),
+            Vec2::new(2.0, 6.0),
+        ];
+        let region = Region::polygon(8, 8, &square);
+        assert_eq!(region.len(), 16);
+        assert!(region.contains(2, 2) && region.contains(5, 5));
+        assert!(!region.contains(1, 2) && !region.contains(6, 5));
+
+        // The pentagon in the middle of a star is crossed twice, so it is
+        // outside, while the points are inside.
+        let star = Region::polygon(20, 20, &pentagram(Vec2::splat(10.0), 9.0));
+        assert!(!star.contains(9, 9));
+        assert!(star.contains(9, 16));
+    }
+
+    #[test]
+    fn disc_holds_the_cells_whose_centres_are_within_the_radius() {
+        let disc = Region::disc(10, 10, Vec2::splat(5.0), 2.0);
+        assert!(disc.contains(4, 4) && disc.contains(5, 6));
+        assert!(!disc.contains(5, 7) && !disc.contains(3, 3));
+        assert_eq!(disc.len(), 12);
+    }
+
+    #[test]
+    fn corners_are_triangles_in_their_corner() {
+        let config = GridConfig::new(10, 10);
+        let corners = [
+            (Dir::SW, (0, 0), (9, 9)),
+            (Dir::SE, (9, 0), (0, 9)),
+            (Dir::NW, (0, 9), (9, 0)),
+            (Dir::NE, (9, 9), (0, 0)),
+        ];
+        for (dir, corner, opposite) in corners {
+            let region = Region::from(dir.clone(), Some(2), &config);
+            assert!(region.contains(corner.0, corner.1), "{dir:?}");
+            assert!(!region.contains(opposite.0, opposite.1), "{dir:?}");
+            // Legs of four cells, with the cells on the diagonal.
+            assert_eq!(region.len(), 10, "{dir:?}");
+        }
+        let south = Region::from(Dir::S, Some(2), &config);
+        assert_eq!(south.len(), 20);
+        assert!(south.cells().all(|(_, y)| y < 2));
+    }
+
+    #[test]
+    fn union_and_minus() {
+        let left = Region::from_fn(4, 4, |x, _| x < 2);
+        let bottom = Region::from_fn(4, 4, |_, y| y < 2);
+        assert_eq!(left.clone().union(&bottom).len(), 12);
+        let corner = left.minus(&bottom);
+        assert_eq!(corner.len(), 4);
+        assert!(corner.cells().all(|(x, y)| x < 2 && y >= 2));
+    }
+
+    #[test]
+    fn layouts_give_each_side_its_own_ground() {
+        let sizes = [(8, 8), (10, 6), (6, 10), (16, 9), (32, 32)];
+        for layout in RegionLayout::ALL {
+            for (width, height) in sizes {
+                let config = GridConfig {
+                    regions: layout,
+                    ..GridConfig::new(width, height)
+                };
+                let player = config.region(RegionOwner::Player);
+                let ai = config.region(RegionOwner::AI);
+                let none = config.region(RegionOwner::None);
+                let at = format!("{layout} on {width}x{height}");
+                assert!(!player.is_empty() && !ai.is_empty(), "{at}");
+                assert!(player.clone().minus(&ai) == player, "{at} overlaps");
+                assert_eq!(player.len() + ai.len() + none.len(), width * height, "{at}");
+            }
+        }
+    }
+}
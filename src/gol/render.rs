@@ -0,0 +1,379 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use rand::prelude::*;
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    gol::{
+        self,
+        board::Board,
+        cell::{CellState, CellType, RegionOwner},
+        elements::Elements,
+        grid::{GridConfig, setup_grid},
+        rng::GameRng,
+        rule::Rules,
+        stats::CellStats,
+        terrain::Terrain,
+    },
+    screens::Screen,
+};
+
+/// The image the [`Board`] is drawn into, one pixel per cell, or two side by
+/// side on hexagonal grids so odd rows can be shifted by half a cell.
+#[derive(Resource)]
+pub struct BoardTexture {
+    pub image: Handle<Image>,
+    /// Which of [`Elements::colors`] each cell is drawn with, so neighbouring
+    /// cells of the same kind don't look like one flat block.
+    shades: Vec<u8>,
+}
+
+#[derive(Component)]
+struct BoardSprite;
+
+/// Tints the cells of each side's region, above the board.
+#[derive(Component)]
+struct RegionOverlay;
+
+/// Whether the [`RegionOverlay`] is shown.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShowRegions(pub bool);
+
+const PLAYER_REGION_TINT: [u8; 4] = [60, 120, 255, 70];
+const AI_REGION_TINT: [u8; 4] = [255, 70, 40, 70];
+
+/// What the board texture shows.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Each cell in the colour of its element.
+    #[default]
+    Elements,
+    /// Live cells by how many generations they have been alive.
+    Age,
+    /// How often cells were recently born or died.
+    Activity,
+    /// How often cells were recently taken over by another element.
+    Conflict,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 4] = [
+        RenderMode::Elements,
+        RenderMode::Age,
+        RenderMode::Activity,
+        RenderMode::Conflict,
+    ];
+
+    /// The next mode in [`RenderMode::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+}
+
+impl fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RenderMode::Elements => "Elements",
+            RenderMode::Age => "Age",
+            RenderMode::Activity => "Activity",
+            RenderMode::Conflict => "Conflict",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Age at which a cell is drawn in the hottest colour.
+const OLD_AGE: f32 = 100.0;
+
+/// Spawns one sprite covering the whole grid, textured with the board.
+fn spawn_board_sprite(
+    mut commands: Commands,
+    config: Res<GridConfig>,
+    board: Res<Board>,
+    mut images: ResMut<Assets<Image>>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<RenderMode>,
+    stats: Res<CellStats>,
+    rules: Res<Rules>,
+    show_regions: Res<ShowRegions>,
+) {
+    let image = board_image(&board);
+    let shades = (0..board.width() * board.height())
+        .map(|_| rng.cosmetics.r#gen())
+        .collect();
+    let texture = BoardTexture {
+        image: images.add(image),
+        shades,
+    };
+    if let Some(image) = images.get_mut(&texture.image) {
+        draw(
+            &board,
+            &rules.elements,
+            &texture.shades,
+            *mode,
+            &stats,
+            image,
+        );
+    }
+
+    // Cell centres sit on `GridConfig::grid_to_world`, so the sprite is
+    // shifted by half a cell, less the extra width of hexagonal grids.
+    let offset = Vec2::new(config.row_shift(1) / 2.0 - 0.5, -0.5) * config.cell_size;
+    commands.spawn((
+        Name::new("Board"),
+        BoardSprite,
+        StateScoped(Screen::Gameplay),
+        Sprite {
+            image: texture.image.clone(),
+            custom_size: Some(config.world_size()),
+            ..default()
+        },
+        Transform::from_translation(offset.extend(0.0)),
+    ));
+    commands.insert_resource(texture);
+
+    // Regions don't change during a match, so the overlay is drawn once.
+    let mut overlay = board_image(&board);
+    if let Some(data) = overlay.data.as_mut() {
+        for_each_pixel(&board, data, |pixel, cell| {
+            let tint = match cell.map(|(x, y)| board.region(x, y)) {
+                Some(RegionOwner::Player) => PLAYER_REGION_TINT,
+                Some(RegionOwner::AI) => AI_REGION_TINT,
+                _ => [0; 4],
+            };
+            pixel.copy_from_slice(&tint);
+        });
+    }
+    commands.spawn((
+        Name::new("Region Overlay"),
+        RegionOverlay,
+        StateScoped(Screen::Gameplay),
+        Sprite {
+            image: images.add(overlay),
+            custom_size: Some(config.world_size()),
+            ..default()
+        },
+        Transform::from_translation(offset.extend(0.5)),
+        region_visibility(*show_regions),
+    ));
+}
+
+fn region_visibility(show: ShowRegions) -> Visibility {
+    if show.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn update_region_overlay(
+    show: Res<ShowRegions>,
+    mut overlay: Query<&mut Visibility, With<RegionOverlay>>,
+) {
+    if !show.is_changed() {
+        return;
+    }
+    for mut visibility in &mut overlay {
+        *visibility = region_visibility(*show);
+    }
+}
+
+/// A transparent image the size of the board's texture.
+fn board_image(board: &Board) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: pixels_per_row(board) as u32,
+            height: board.height() as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    // Keep the cells crisp when zoomed in.
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// Redraws the texture whenever the board or the render mode changed.
+fn update_board_texture(
+    board: Res<Board>,
+    texture: Option<Res<BoardTexture>>,
+    mode: Res<RenderMode>,
+    stats: Res<CellStats>,
+    rules: Res<Rules>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !board.is_changed() && !mode.is_changed() {
+        return;
+    }
+    let Some(texture) = texture else {
+        return;
+    };
+    if let Some(image) = images.get_mut(&texture.image) {
+        draw(
+            &board,
+            &rules.elements,
+            &texture.shades,
+            *mode,
+            &stats,
+            image,
+        );
+    }
+}
+
+/// Writes every cell of `board` into the pixels of `image`, which must have
+/// the board's size. Board row 0 is at the bottom, image row 0 at the top.
+fn draw(
+    board: &Board,
+    elements: &Elements,
+    shades: &[u8],
+    mode: RenderMode,
+    stats: &CellStats,
+    image: &mut Image,
+) {
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+    if shades.len() != board.width() * board.height() {
+        return;
+    }
+    let mut palette = Palette::new(elements);
+    for_each_pixel(board, data, |pixel, cell| {
+        let Some((x, y)) = cell else {
+            pixel.copy_from_slice(&palette.color(CellState::Dead, Terrain::Open, 0));
+            return;
+        };
+        let i = board.index(x, y);
+        let state = board.cells()[i];
+        let terrain = board.terrain(x, y);
+        // Counters are missing until the board has stepped once.
+        let color = match mode {
+            _ if !terrain.holds_cells() => palette.color(state, terrain, 0),
+            RenderMode::Age if state.is_alive() => {
+                let age = stats.age.get(i).copied().unwrap_or(0);
+                heat(0.1 + 0.9 * (age as f32 / OLD_AGE).min(1.0))
+            }
+            RenderMode::Activity => {
+                heat(stats.activity.get(i).copied().unwrap_or(0) as f32 / 255.0)
+            }
+            RenderMode::Conflict => {
+                heat(stats.conflict.get(i).copied().unwrap_or(0) as f32 / 255.0)
+            }
+            _ => palette.color(state, terrain, shades[i]),
+        };
+        pixel.copy_from_slice(&color);
+    });
+}
+
+/// Calls `f` with every pixel of an image of the board's texture size and
+/// the cell it shows, if any. Board row 0 is at the bottom, image row 0 at
+/// the top.
+fn for_each_pixel(
+    board: &Board,
+    data: &mut [u8],
+    mut f: impl FnMut(&mut [u8], Option<(usize, usize)>),
+) {
+    let (width, height) = (board.width(), board.height());
+    let row_len = pixels_per_row(board);
+    if data.len() != row_len * height * 4 {
+        return;
+    }
+    let hexagonal = board.neighbourhood().is_hexagonal();
+    for (row, pixels) in data.chunks_exact_mut(row_len * 4).enumerate() {
+        let y = height - 1 - row;
+        for (px, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+            let x = if hexagonal {
+                // Odd rows start one pixel, half a cell, further right.
+                px.checked_sub(y % 2).map(|px| px / 2)
+            } else {
+                Some(px)
+            };
+            f(pixel, x.filter(|&x| x < width).map(|x| (x, y)));
+        }
+    }
+}
+
+fn pixels_per_row(board: &Board) -> usize {
+    if board.neighbourhood().is_hexagonal() {
+        2 * board.width() + 1
+    } else {
+        board.width()
+    }
+}
+
+/// Black through red and yellow to white as `t` goes from 0 to 1.
+fn heat(t: f32) -> [u8; 4] {
+    let channel = |from: f32| ((t * 3.0 - from).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(0.0), channel(1.0), channel(2.0), 255]
+}
+
+/// [`Elements::colors`] as sRGB bytes, converted once per kind.
+struct Palette<'a> {
+    elements: &'a Elements,
+    /// By [`CellType::index`].
+    colors: Vec<Option<Vec<[u8; 4]>>>,
+    /// By kind and generations since death.
+    decay: HashMap<(CellType, u8), [u8; 4]>,
+    /// [`Terrain::color`] by terrain.
+    ground: HashMap<Terrain, Option<[u8; 4]>>,
+}
+
+impl<'a> Palette<'a> {
+    fn new(elements: &'a Elements) -> Self {
+        Self {
+            elements,
+            colors: vec![None; u8::MAX as usize + 1],
+            decay: HashMap::new(),
+            ground: HashMap::new(),
+        }
+    }
+
+    /// The colour of a cell in `state` on `terrain`. Dead cells show the
+    /// colour of the ground, if it has one.
+    fn color(&mut self, state: CellState, terrain: Terrain, shade: u8) -> [u8; 4] {
+        if state.is_dead()
+            && let Some(ground) = *self
+                .ground
+                .entry(terrain)
+                .or_insert_with(|| terrain.color().map(|color| color.to_srgba().to_u8_array()))
+        {
+            return ground;
+        }
+        if let CellState::Decaying(kind, age) = state {
+            return *self.decay.entry((kind, age)).or_insert_with(|| {
+                self.elements
+                    .decay_color(kind, age)
+                    .to_srgba()
+                    .to_u8_array()
+            });
+        }
+        let kind = state.kind();
+        let colors = self.colors[kind.index()].get_or_insert_with(|| {
+            self.elements
+                .colors(kind)
+                .iter()
+                .map(|color| color.to_srgba().to_u8_array())
+                .collect()
+        });
+        colors[shade as usize % colors.len()]
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RenderMode>()
+        .init_resource::<ShowRegions>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            spawn_board_sprite.after(setup_grid),
+        )
+        .add_systems(
+            PostUpdate,
+            (update_board_texture, update_region_overlay).run_if(in_state(Screen::Gameplay)),
+        );
+}
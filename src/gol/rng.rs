@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng, prelude::*, rngs::StdRng};
+
+/// Seeded randomness for a match.
+///
+/// Every consumer draws from its own stream, so e.g. picking a cell colour
+/// never shifts the dice of the rules, and a match can be replayed
+/// bit-for-bit from [`GameRng::seed`].
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    seed: u64,
+    /// Keep the seed when a new match starts instead of rolling a new one.
+    fixed: bool,
+    /// Coin flips of the simulation rules.
+    pub rules: StdRng,
+    /// Choices of the CPU player.
+    pub ai: StdRng,
+    /// Purely visual choices such as cell shades.
+    pub cosmetics: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::thread_rng().r#gen(), false)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64, fixed: bool) -> Self {
+        Self {
+            seed,
+            fixed,
+            rules: stream(seed, 1),
+            ai: stream(seed, 2),
+            cosmetics: stream(seed, 3),
+        }
+    }
+
+    /// A generator that replays the same seed in every match.
+    pub fn fixed(seed: u64) -> Self {
+        Self::new(seed, true)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Rewinds every stream to the start of the seed, rolling a new seed
+    /// first unless it is fixed.
+    pub fn restart(&mut self) {
+        let seed = if self.fixed {
+            self.seed
+        } else {
+            rand::thread_rng().r#gen()
+        };
+        *self = Self::new(seed, self.fixed);
+    }
+
+    /// Reads `--seed N` (or `--seed=N`) or `--daily` from the command line
+    /// arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = if arg == "--seed" {
+                args.next()?
+            } else if let Some(value) = arg.strip_prefix("--seed=") {
+                value.to_string()
+            } else if arg == "--daily" {
+                return daily_seed().map(Self::fixed);
+            } else {
+                continue;
+            };
+            let Ok(seed) = value.parse() else {
+                warn!("Ignoring invalid seed '{value}', expected a number");
+                return None;
+            };
+            return Some(Self::fixed(seed));
+        }
+        None
+    }
+}
+
+/// Derives an independent stream from the match seed.
+fn stream(seed: u64, index: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Randomness of one cell in one generation, derived from a seed drawn
+/// once per generation and the cell's index.
+///
+/// Cells don't share a stream, so the board can be stepped in any order, or
+/// in parallel, and still come out the same for the same seed.
+pub struct CellRng(u64);
+
+impl CellRng {
+    pub fn new(seed: u64, index: usize) -> Self {
+        Self::from_key(seed, index as u64)
+    }
+
+    /// For cells of an unbounded world, which have no index.
+    pub fn at(seed: u64, pos: IVec2) -> Self {
+        Self::from_key(seed, (pos.x as u32 as u64) << 32 | pos.y as u32 as u64)
+    }
+
+    fn from_key(seed: u64, key: u64) -> Self {
+        Self(seed ^ key.wrapping_mul(0xD1B5_4A32_D192_ED03))
+    }
+}
+
+impl RngCore for CellRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The same seed for everyone on the same (UTC) day.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn daily_seed() -> Option<u64> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(now.as_secs() / (24 * 60 * 60))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn daily_seed() -> Option<u64> {
+    warn!("Daily seeds are not available in the web build");
+    None
+}
+
+/// Starts the streams over for a new match.
+pub fn restart_rng(mut rng: ResMut<GameRng>) {
+    rng.restart();
+    info!("Match seed: {}", rng.seed());
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(GameRng::from_args(std::env::args()).unwrap_or_default());
+}
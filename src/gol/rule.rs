@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use std::{fmt, str::FromStr};
+
+use crate::gol::{
+    self,
+    cell::{CellState, CellType},
+    elements::Elements,
+    topology::Topology,
+};
+
+/// Named rules offered by the settings menu.
+pub const RULE_PRESETS: &[(&str, &str)] = &[
+    ("Conway", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Day & Night", "B3678/S34678"),
+    ("Seeds", "B2/S"),
+    ("Life without Death", "B3/S012345678"),
+    ("Maze", "B3/S12345"),
+    ("2x2", "B36/S125"),
+];
+
+/// The rules the simulation runs under.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Rules {
+    /// The rule shared by every element.
+    pub rule: Rule,
+    /// Let each element live by its own rule from [`Elements::rule`]
+    /// instead of the shared one.
+    pub per_element: bool,
+    /// How neighbouring elements fight over a cell.
+    pub battle: BattleRule,
+    /// Let dead cells go through their element's decay stages, taking up
+    /// their place, before they are gone.
+    pub decay: bool,
+    /// The elements and who beats whom.
+    pub elements: Elements,
+}
+
+/// How the rock-paper-scissors battle between elements is resolved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BattleRule {
+    /// A cell takes the winner of a battle between its own kind (or the most
+    /// frequent neighbour when born) and another neighbour kind, with coin
+    /// flips deciding which kinds meet and who wins a draw.
+    #[default]
+    TopTwo,
+    /// The two-phase rule of the `brainstorm/index.html` prototype: first
+    /// the winner of the two most frequent kinds around each cell converts
+    /// the losing cells next to it, then the Life rule runs with births
+    /// taking the majority kind.
+    MajorityConversion,
+}
+
+impl BattleRule {
+    pub const ALL: [BattleRule; 2] = [BattleRule::TopTwo, BattleRule::MajorityConversion];
+
+    /// Whether boards with `topology` can fight by this rule. The world
+    /// around a [`Topology::Unbounded`] grid only steps with
+    /// [`BattleRule::TopTwo`].
+    pub fn works_with(self, topology: Topology) -> bool {
+        self == BattleRule::TopTwo || topology != Topology::Unbounded
+    }
+
+    /// The next battle rule in [`BattleRule::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+}
+
+impl fmt::Display for BattleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BattleRule::TopTwo => "Top two",
+            BattleRule::MajorityConversion => "Conversion",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Rules {
+    /// The rule that decides whether a cell of `kind` is born or survives.
+    pub fn for_kind(&self, kind: CellType) -> Rule {
+        if self.per_element {
+            self.elements.rule(kind).unwrap_or(self.rule)
+        } else {
+            self.rule
+        }
+    }
+
+    /// The state of a cell that a step took from `before` to `after`, with
+    /// decay applied if it is on.
+    pub fn decay(&self, before: CellState, after: CellState) -> CellState {
+        if self.decay {
+            self.elements.decay(before, after)
+        } else {
+            after
+        }
+    }
+
+    /// Whether any element could be born with `alive_neighbors` live
+    /// neighbours, before knowing which element it would be.
+    pub fn may_birth(&self, alive_neighbors: usize) -> bool {
+        self.rule.births(alive_neighbors)
+            || (self.per_element
+                && self
+                    .elements
+                    .iter()
+                    .filter_map(|element| element.rule)
+                    .any(|rule| rule.births(alive_neighbors)))
+    }
+}
+
+/// A Life-like birth/survival rule in B/S notation, e.g. `B3/S23`.
+///
+/// Bit `n` of each mask is set when a cell with `n` live neighbours is born
+/// or survives. Counts go up to [`MAX_COUNT`], enough for the largest
+/// [`Neighbourhood`](crate::gol::neighbourhood::Neighbourhood).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u64,
+    survive: u64,
+}
+
+/// The highest neighbour count a [`Rule`] can name.
+pub const MAX_COUNT: usize = 48;
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::CONWAY
+    }
+}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule::from_counts(&[3], &[2, 3]);
+
+    /// Builds a rule from the neighbour counts that give birth and survival.
+    pub const fn from_counts(birth: &[u8], survive: &[u8]) -> Rule {
+        Rule {
+            birth: counts_mask(birth),
+            survive: counts_mask(survive),
+        }
+    }
+
+    pub fn births(&self, alive_neighbors: usize) -> bool {
+        alive_neighbors <= MAX_COUNT && self.birth & (1 << alive_neighbors) != 0
+    }
+
+    pub fn survives(&self, alive_neighbors: usize) -> bool {
+        alive_neighbors <= MAX_COUNT && self.survive & (1 << alive_neighbors) != 0
+    }
+
+    /// Reads `--rule RULESTRING` (or `--rule=RULESTRING`) from the command
+    /// line arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = if arg == "--rule" {
+                args.next()?
+            } else if let Some(value) = arg.strip_prefix("--rule=") {
+                value.to_string()
+            } else {
+                continue;
+            };
+            return match value.parse() {
+                Ok(rule) => Some(rule),
+                Err(err) => {
+                    warn!("Ignoring rule '{value}': {err}");
+                    None
+                }
+            };
+        }
+        None
+    }
+
+    /// The name of the matching [`RULE_PRESETS`] entry, if any.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        RULE_PRESETS
+            .iter()
+            .find(|(_, rulestring)| rulestring.parse().ok() == Some(*self))
+            .map(|(name, _)| *name)
+    }
+
+    /// The next entry of [`RULE_PRESETS`], wrapping around. Rules that are
+    /// not a preset continue from the first one.
+    pub fn cycle_preset(&self, step: i32) -> Self {
+        let presets: Vec<Rule> = RULE_PRESETS
+            .iter()
+            .map(|(_, rulestring)| rulestring.parse().unwrap_or_default())
+            .collect();
+        gol::cycle(&presets, *self, step)
+    }
+}
+
+const fn counts_mask(counts: &[u8]) -> u64 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < counts.len() {
+        mask |= 1 << counts[i];
+        i += 1;
+    }
+    mask
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// The rulestring isn't a `B` part and an `S` part, with or without a
+    /// `/` between them.
+    Format,
+    /// A neighbour count outside `0..=8`.
+    Digit(char),
+    /// An entry of a comma-separated count list that isn't a count up to
+    /// [`MAX_COUNT`] or an ascending range of them.
+    Count(String),
+    /// `B0` rules make empty space come alive every generation.
+    BirthOnZero,
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleParseError::Format => write!(f, "expected a rule like B3/S23 or B3S23"),
+            RuleParseError::Digit(c) => write!(f, "'{c}' is not a neighbour count (0-8)"),
+            RuleParseError::Count(s) => {
+                write!(f, "'{s}' is not a neighbour count (0-{MAX_COUNT}) or range")
+            }
+            RuleParseError::BirthOnZero => write!(f, "B0 rules are not supported"),
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    /// Parses `B3/S23` style rulestrings, with or without the slash. The
+    /// parts may come in either order and are case-insensitive. For
+    /// neighbourhoods with more than eight cells, counts can be listed with
+    /// commas and ranges, e.g. `B34-45/S34-48`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (first, second) = match s.split_once('/') {
+            Some(parts) => parts,
+            None => {
+                // The second part starts at the next `B` or `S`.
+                let split = s
+                    .char_indices()
+                    .skip(1)
+                    .find(|(_, c)| matches!(c.to_ascii_uppercase(), 'B' | 'S'))
+                    .map(|(i, _)| i)
+                    .ok_or(RuleParseError::Format)?;
+                s.split_at(split)
+            }
+        };
+        let mut birth = None;
+        let mut survive = None;
+        for part in [first, second] {
+            let part = part.trim();
+            let mut chars = part.chars();
+            let target = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') if birth.is_none() => &mut birth,
+                Some('S') if survive.is_none() => &mut survive,
+                _ => return Err(RuleParseError::Format),
+            };
+            let counts = chars.as_str();
+            let mask = if counts.contains([',', '-']) {
+                parse_count_list(counts)?
+            } else {
+                let mut mask = 0u64;
+                for c in chars {
+                    let n = c.to_digit(10).filter(|n| *n <= 8);
+                    let n = n.ok_or(RuleParseError::Digit(c))?;
+                    mask |= 1 << n;
+                }
+                mask
+            };
+            *target = Some(mask);
+        }
+        let (Some(birth), Some(survive)) = (birth, survive) else {
+            return Err(RuleParseError::Format);
+        };
+        if birth & 1 != 0 {
+            return Err(RuleParseError::BirthOnZero);
+        }
+        Ok(Rule { birth, survive })
+    }
+}
+
+/// Parses counts like `3,5-7` into a mask.
+fn parse_count_list(list: &str) -> Result<u64, RuleParseError> {
+    let mut mask = 0u64;
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let count = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n <= MAX_COUNT)
+                .ok_or_else(|| RuleParseError::Count(entry.to_string()))
+        };
+        let (low, high) = match entry.split_once('-') {
+            Some((low, high)) => (count(low)?, count(high)?),
+            None => (count(entry)?, count(entry)?),
+        };
+        if low > high {
+            return Err(RuleParseError::Count(entry.to_string()));
+        }
+        for n in low..=high {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |mask: u64| -> String {
+            let counts: Vec<usize> = (0..=MAX_COUNT).filter(|n| mask & (1 << n) != 0).collect();
+            if counts.iter().all(|&n| n <= 8) {
+                return counts.iter().map(|n| n.to_string()).collect();
+            }
+            // Runs of consecutive counts as ranges.
+            let mut runs: Vec<(usize, usize)> = Vec::new();
+            for n in counts {
+                match runs.last_mut() {
+                    Some((_, high)) if *high + 1 == n => *high = n,
+                    _ => runs.push((n, n)),
+                }
+            }
+            let runs: Vec<String> = runs
+                .iter()
+                .map(|&(low, high)| match high - low {
+                    0 => low.to_string(),
+                    1 => format!("{low},{high}"),
+                    _ => format!("{low}-{high}"),
+                })
+                .collect();
+            runs.join(",")
+        };
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survive))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings_with_and_without_slash() {
+        let highlife = Rule::from_counts(&[3, 6], &[2, 3]);
+        for rulestring in ["B36/S23", "B36S23", "s23/b36", "S23B36", " b36s23 "] {
+            assert_eq!(rulestring.parse(), Ok(highlife), "{rulestring}");
+        }
+        assert_eq!("B2/S".parse(), Ok(Rule::from_counts(&[2], &[])));
+        assert_eq!("B2S".parse(), Ok(Rule::from_counts(&[2], &[])));
+    }
+
+    #[test]
+    fn parses_count_lists_with_commas_and_ranges() {
+        let rule: Rule = "B34-45/S34-48".parse().unwrap();
+        let range = |low, high| (low..=high).collect::<Vec<u8>>();
+        assert_eq!(rule, Rule::from_counts(&range(34, 45), &range(34, 48)));
+
+        let rule: Rule = "B3,5-7/S2,3,10".parse().unwrap();
+        assert_eq!(rule, Rule::from_counts(&[3, 5, 6, 7], &[2, 3, 10]));
+        assert_eq!(rule.to_string().parse(), Ok(rule));
+    }
+
+    #[test]
+    fn rejects_malformed_count_lists() {
+        for rulestring in [
+            "B34-45/S34-58",
+            "B5-3/S2",
+            "B3,x/S2",
+            "B3-/S2",
+            "B,3-4-5/S2",
+        ] {
+            assert!(
+                matches!(rulestring.parse::<Rule>(), Err(RuleParseError::Count(_))),
+                "{rulestring}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rulestrings() {
+        for rulestring in ["", "B3", "23/3", "B3B3", "B3/S2/3"] {
+            assert!(rulestring.parse::<Rule>().is_err(), "{rulestring}");
+        }
+    }
+}
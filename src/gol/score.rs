@@ -3,7 +3,11 @@ use bevy::prelude::*;
 use crate::{
     AppSystems, PausableSystems,
     gol::{
-        cell::{Cell, CellType},
+        board::Board,
+        cell::{CellType, Owner},
+        history::Snapshot,
+        stability::Stability,
+        territory::Territories,
         ui::GameMenuRoot,
     },
     screens::Screen,
@@ -16,30 +20,61 @@ pub struct PlayerScore(pub i32);
 #[derive(Resource, Default)]
 pub struct AiScore(pub i32);
 
+/// Water cells the player has placed by hand this match. Water fired by
+/// their towers is free.
+#[derive(Resource, Clone, Copy, Default)]
+pub struct WaterSpent(pub u32);
+
+/// Enemy cells each owner has converted to their own this match.
+#[derive(Resource, Clone, Default)]
+pub struct Kills([u32; Owner::MAX]);
+
+impl Kills {
+    pub fn of(&self, owner: Owner) -> u32 {
+        self.0.get(owner.index()).copied().unwrap_or(0)
+    }
+
+    /// Credits the conversions of one generation step from `before` to
+    /// `after`.
+    pub fn record(&mut self, before: &Snapshot, after: &Board) {
+        let cells = before.cells().iter().zip(after.cells());
+        let owners = before.owners().iter().zip(after.owners());
+        for ((was, is), (&victim, &killer)) in cells.zip(owners) {
+            if was.is_alive()
+                && is.is_alive()
+                && victim != killer
+                && victim != Owner::NONE
+                && let Some(kills) = self.0.get_mut(killer.index())
+            {
+                *kills += 1;
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 struct AiScoreDisplay;
 
 #[derive(Component)]
 struct PlayerScoreDisplay;
 
+#[derive(Component)]
+struct StabilityDisplay;
+
+#[derive(Component)]
+struct TerritoryDisplay;
+
+/// The player scores the trees they own less the water they spent, the AI
+/// every cell it owns.
 fn update_scores(
-    cells: Query<&Cell>,
+    board: Res<Board>,
+    spent: Res<WaterSpent>,
     mut player_score: ResMut<PlayerScore>,
     mut ai_score: ResMut<AiScore>,
 ) {
-    let mut tree: i32 = 0;
-    let mut water: i32 = 0;
-    let mut fire: i32 = 0;
-    for cell in cells.iter() {
-        match cell.state.kind() {
-            CellType::Tree => tree += 1,
-            CellType::Water => water += 1,
-            CellType::Fire => fire += 1,
-            _ => {}
-        }
-    }
-    player_score.0 = tree - water;
-    ai_score.0 = fire;
+    let tree = board.count_owned_kind(Owner::PLAYER, CellType::TREE) as i32;
+    player_score.0 = tree - spent.0 as i32;
+    ai_score.0 = board.count_owned(Owner::AI) as i32;
 }
 const INSTRUCTIONS: &str = r#"Protect the plants
 Don't waste water.
@@ -48,6 +83,17 @@ To add watter:
 Click a pattern to and press the keys w,a,s,d to choose a direction.
 Click to place the pattern.
 
+Press space to pause or resume.
+Press left/right to step back/forward,
+hold shift for 10 generations.
+Press -/= to slow down/speed up.
+Press j to jump 1024 generations
+(unbounded edges, one element only).
+Towers fire on their own, and fall
+when enemy cells overrun them.
+Choose Paint to draw walls and holes,
+then Save Map to play on it again.
+
 Press p to pause or quit to title."#;
 
 fn spawn_ui_scores(mut commands: Commands) {
@@ -59,6 +105,9 @@ fn spawn_ui_scores(mut commands: Commands) {
             root.spawn(build_ui_score("Player"))
                 .insert(PlayerScoreDisplay);
             root.spawn(build_ui_score("AI")).insert(AiScoreDisplay);
+            root.spawn(build_instructions("Board: active"))
+                .insert(StabilityDisplay);
+            root.spawn(build_instructions("")).insert(TerritoryDisplay);
         });
 }
 fn build_instructions(instructions: &str) -> (Text, TextFont, TextColor, TextLayout) {
@@ -87,24 +136,89 @@ fn build_ui_score(name: &str) -> (Text, TextFont, TextColor, TextLayout) {
 fn update_score_ui(
     player_score: Res<PlayerScore>,
     ai_score: Res<AiScore>,
+    kills: Res<Kills>,
     mut player_text: Query<&mut Text, With<PlayerScoreDisplay>>,
     mut ai_text: Query<&mut Text, (With<AiScoreDisplay>, Without<PlayerScoreDisplay>)>,
 ) {
     if let Ok(mut text) = player_text.single_mut() {
-        text.0 = format!("Player Score: {}", player_score.0);
+        text.0 = format!(
+            "Player Score: {} ({} kills)",
+            player_score.0,
+            kills.of(Owner::PLAYER)
+        );
     }
     if let Ok(mut text) = ai_text.single_mut() {
-        text.0 = format!("AI Score: {}", ai_score.0);
+        text.0 = format!("AI Score: {} ({} kills)", ai_score.0, kills.of(Owner::AI));
+    }
+}
+
+fn reset_kills(mut kills: ResMut<Kills>, mut spent: ResMut<WaterSpent>) {
+    *kills = Kills::default();
+    *spent = WaterSpent::default();
+}
+
+fn update_stability_ui(
+    stability: Res<Stability>,
+    mut stability_text: Query<&mut Text, With<StabilityDisplay>>,
+) {
+    let status = match stability.period() {
+        Some(period) => format!("Board: stable (period {period})"),
+        None => "Board: active".to_string(),
+    };
+    if let Ok(mut text) = stability_text.single_mut()
+        && text.0 != status
+    {
+        text.0 = status;
+    }
+}
+
+/// One line per territory with who holds it, then the points for holding
+/// territory so far.
+fn update_territory_ui(
+    territories: Res<Territories>,
+    mut territory_text: Query<&mut Text, With<TerritoryDisplay>>,
+) {
+    let mut status = String::new();
+    for territory in territories.iter() {
+        let holder = match territory.controller() {
+            Some(Owner::PLAYER) => "you",
+            Some(Owner::AI) => "AI",
+            Some(_) => "other",
+            None => "contested",
+        };
+        status += &format!(
+            "{}: {holder} ({} vs {})\n",
+            territory.name,
+            territory.population(Owner::PLAYER),
+            territory.population(Owner::AI)
+        );
+    }
+    status += &format!(
+        "Territory points: {} vs {}",
+        territories.points(Owner::PLAYER),
+        territories.points(Owner::AI)
+    );
+    if let Ok(mut text) = territory_text.single_mut()
+        && text.0 != status
+    {
+        text.0 = status;
     }
 }
 
 pub(super) fn plugin(app: &mut App) {
     app.insert_resource(PlayerScore::default())
         .insert_resource(AiScore::default())
-        .add_systems(OnEnter(Screen::Gameplay), spawn_ui_scores)
+        .init_resource::<Kills>()
+        .init_resource::<WaterSpent>()
+        .add_systems(OnEnter(Screen::Gameplay), (spawn_ui_scores, reset_kills))
         .add_systems(
             FixedUpdate,
-            (update_scores, update_score_ui)
+            (
+                update_scores,
+                update_score_ui,
+                update_stability_ui,
+                update_territory_ui,
+            )
                 .chain()
                 .in_set(AppSystems::Update)
                 .in_set(PausableSystems),
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::gol::{
+    board::next_top_two,
+    cell::{CellState, CellType},
+    hashlife::HashLife,
+    neighbourhood::Neighbourhood,
+    rng::CellRng,
+    rule::Rules,
+    terrain::Terrain,
+};
+
+/// Cells on a side of a chunk.
+const CHUNK: i32 = 32;
+
+type Chunk = Box<[CellState; (CHUNK * CHUNK) as usize]>;
+
+/// An unbounded world stored as square chunks; only chunks with live or
+/// decaying cells are kept.
+///
+/// Behind a board with [`Topology::Unbounded`], it keeps the cells that
+/// have left the visible grid alive. It always fights with
+/// [`BattleRule::TopTwo`].
+///
+/// [`Topology::Unbounded`]: crate::gol::topology::Topology::Unbounded
+/// [`BattleRule::TopTwo`]: crate::gol::rule::BattleRule::TopTwo
+#[derive(Clone, Default, PartialEq)]
+pub struct SparseWorld {
+    chunks: HashMap<IVec2, Chunk>,
+}
+
+impl SparseWorld {
+    pub fn get(&self, pos: IVec2) -> CellState {
+        let (chunk, offset) = split(pos);
+        self.chunks
+            .get(&chunk)
+            .map_or(CellState::Dead, |cells| cells[offset])
+    }
+
+    pub fn set(&mut self, pos: IVec2, state: CellState) {
+        let (chunk, offset) = split(pos);
+        match self.chunks.get_mut(&chunk) {
+            Some(cells) => cells[offset] = state,
+            None if !state.is_dead() => {
+                let mut cells = empty_chunk();
+                cells[offset] = state;
+                self.chunks.insert(chunk, cells);
+            }
+            None => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Number of chunks allocated.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Positions and states of the live cells.
+    pub fn alive(&self) -> impl Iterator<Item = (IVec2, CellState)> + '_ {
+        self.chunks.iter().flat_map(|(&chunk, cells)| {
+            cells.iter().enumerate().filter_map(move |(i, &state)| {
+                let offset = IVec2::new(i as i32 % CHUNK, i as i32 / CHUNK);
+                state.is_alive().then_some((chunk * CHUNK + offset, state))
+            })
+        })
+    }
+
+    /// Advances the world one generation, with each cell's coin flips drawn
+    /// from `seed` and its position, and its ground from `terrain`.
+    pub fn step(
+        &mut self,
+        rules: &Rules,
+        seed: u64,
+        neighbourhood: Neighbourhood,
+        terrain: impl Fn(IVec2) -> Terrain,
+    ) {
+        // Births can spill into the chunks around a live one, as no
+        // neighbourhood reaches further than a chunk.
+        let mut active = HashSet::new();
+        for &chunk in self.chunks.keys() {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    active.insert(chunk + IVec2::new(dx, dy));
+                }
+            }
+        }
+        let mut next = HashMap::with_capacity(active.len());
+        for chunk in active {
+            let cells = self.step_chunk(chunk, rules, seed, neighbourhood, &terrain);
+            if cells.iter().any(|state| !state.is_dead()) {
+                next.insert(chunk, cells);
+            }
+        }
+        self.chunks = next;
+    }
+
+    fn step_chunk(
+        &self,
+        chunk: IVec2,
+        rules: &Rules,
+        seed: u64,
+        neighbourhood: Neighbourhood,
+        terrain: &impl Fn(IVec2) -> Terrain,
+    ) -> Chunk {
+        // The chunk and the ring of cells around it, looked up once.
+        let reach = neighbourhood.reach();
+        let padded_len = CHUNK + 2 * reach;
+        let origin = chunk * CHUNK;
+        let around: [Option<&Chunk>; 9] = std::array::from_fn(|i| {
+            let offset = IVec2::new(i as i32 % 3 - 1, i as i32 / 3 - 1);
+            self.chunks.get(&(chunk + offset))
+        });
+        let mut padded = vec![CellState::Dead; (padded_len * padded_len) as usize];
+        for (i, state) in padded.iter_mut().enumerate() {
+            let pos = IVec2::new(i as i32 % padded_len, i as i32 / padded_len) - reach;
+            let (offset, index) = split(pos);
+            let near = (offset.y + 1) * 3 + offset.x + 1;
+            if let Some(cells) = around[near as usize] {
+                *state = cells[index];
+            }
+        }
+        let at = |x: i32, y: i32| padded[((y + reach) * padded_len + x + reach) as usize];
+
+        // Chunks start on an even row, so rows keep their parity.
+        let offsets = [false, true].map(|odd| neighbourhood.offsets(odd));
+        let mut cells = empty_chunk();
+        let mut alive_neighbors = Vec::with_capacity(offsets[0].len());
+        for (i, next) in cells.iter_mut().enumerate() {
+            let (x, y) = (i as i32 % CHUNK, i as i32 / CHUNK);
+            // Same order as `Board::alive_neighbors`, which breaks ties.
+            alive_neighbors.clear();
+            for offset in &offsets[(y % 2) as usize] {
+                let state = at(x + offset.x, y + offset.y);
+                if state.is_alive() {
+                    alive_neighbors.push(state.kind());
+                }
+            }
+            let state = at(x, y);
+            let pos = origin + IVec2::new(x, y);
+            if !state.is_alive() && alive_neighbors.is_empty() {
+                *next = rules.decay(state, CellState::Dead);
+                continue;
+            }
+            let rng = &mut CellRng::at(seed, pos);
+            let alive = next_top_two(state, &alive_neighbors, terrain(pos), rules, rng);
+            *next = rules.decay(state, alive);
+        }
+        cells
+    }
+
+    /// The only element alive in the world, if there is exactly one.
+    pub fn single_kind(&self) -> Option<CellType> {
+        let mut kinds = self.alive().map(|(_, state)| state.kind());
+        let first = kinds.next()?;
+        kinds.all(|kind| kind == first).then_some(first)
+    }
+
+    /// Advances the world `2^j` generations with [`HashLife`]. Only a world
+    /// of a single element that doesn't decay can jump, as battles and
+    /// decaying cells need every generation; returns whether it did.
+    pub fn jump(&mut self, j: u8, rules: &Rules) -> bool {
+        let Some(kind) = self.single_kind() else {
+            return false;
+        };
+        if rules.decay && rules.elements.decay_len(kind) > 0 {
+            return false;
+        }
+        let mut life = HashLife::new(rules.for_kind(kind), self.alive().map(|(pos, _)| pos));
+        life.jump(j);
+        self.clear();
+        life.for_each_alive(|pos| self.set(pos, CellState::Alive(kind)));
+        true
+    }
+}
+
+/// The chunks that changed between two states of a world, as
+/// `(chunk, before, after)` with `None` for a chunk that wasn't kept.
+#[derive(Default)]
+pub struct WorldDiff {
+    chunks: Vec<(IVec2, Option<Chunk>, Option<Chunk>)>,
+}
+
+impl WorldDiff {
+    pub fn between(before: &SparseWorld, after: &SparseWorld) -> Self {
+        let keys: HashSet<IVec2> = before
+            .chunks
+            .keys()
+            .chain(after.chunks.keys())
+            .copied()
+            .collect();
+        let chunks = keys
+            .into_iter()
+            .filter_map(|key| {
+                let (b, a) = (before.chunks.get(&key), after.chunks.get(&key));
+                (b != a).then(|| (key, b.cloned(), a.cloned()))
+            })
+            .collect();
+        Self { chunks }
+    }
+
+    /// Puts the `before` side of the changes back in `world`.
+    pub fn undo(&self, world: &mut SparseWorld) {
+        for (key, before, _) in &self.chunks {
+            set_chunk(world, *key, before);
+        }
+    }
+
+    /// Puts the `after` side of the changes back in `world`.
+    pub fn redo(&self, world: &mut SparseWorld) {
+        for (key, _, after) in &self.chunks {
+            set_chunk(world, *key, after);
+        }
+    }
+}
+
+fn set_chunk(world: &mut SparseWorld, key: IVec2, cells: &Option<Chunk>) {
+    match cells {
+        Some(cells) => world.chunks.insert(key, cells.clone()),
+        None => world.chunks.remove(&key),
+    };
+}
+
+fn empty_chunk() -> Chunk {
+    Box::new([CellState::Dead; (CHUNK * CHUNK) as usize])
+}
+
+/// The chunk holding `pos` and the index of `pos` within it.
+fn split(pos: IVec2) -> (IVec2, usize) {
+    let chunk = IVec2::new(pos.x.div_euclid(CHUNK), pos.y.div_euclid(CHUNK));
+    let offset = pos - chunk * CHUNK;
+    (chunk, (offset.y * CHUNK + offset.x) as usize)
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    gol::{
+        board::Board,
+        history::{History, Tallies},
+        rng::GameRng,
+        rule::Rules,
+        topology::Topology,
+    },
+    screens::Screen,
+};
+
+/// One notch of the speed slider: a fixed tick length and how many
+/// generations run per tick.
+pub struct SpeedLevel {
+    pub label: &'static str,
+    pub seconds: f64,
+    pub generations: usize,
+}
+
+/// Slowest first. Past 4x the tick stops shrinking and more generations
+/// run per tick instead.
+pub const SPEED_LEVELS: &[SpeedLevel] = &[
+    SpeedLevel {
+        label: "1/4x",
+        seconds: 0.8,
+        generations: 1,
+    },
+    SpeedLevel {
+        label: "1/2x",
+        seconds: 0.4,
+        generations: 1,
+    },
+    SpeedLevel {
+        label: "1x",
+        seconds: 0.2,
+        generations: 1,
+    },
+    SpeedLevel {
+        label: "2x",
+        seconds: 0.1,
+        generations: 1,
+    },
+    SpeedLevel {
+        label: "4x",
+        seconds: 0.05,
+        generations: 1,
+    },
+    SpeedLevel {
+        label: "8x",
+        seconds: 0.05,
+        generations: 2,
+    },
+    SpeedLevel {
+        label: "16x",
+        seconds: 0.05,
+        generations: 4,
+    },
+    SpeedLevel {
+        label: "32x",
+        seconds: 0.05,
+        generations: 8,
+    },
+];
+
+const DEFAULT_SPEED_LEVEL: usize = 2;
+
+/// A jump skips `2^JUMP_LOG2` generations.
+const JUMP_LOG2: u8 = 10;
+
+/// Index into [`SPEED_LEVELS`] of the current simulation speed.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationSpeed(pub usize);
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        Self(DEFAULT_SPEED_LEVEL)
+    }
+}
+
+impl SimulationSpeed {
+    pub fn level(&self) -> &'static SpeedLevel {
+        &SPEED_LEVELS[self.0.min(SPEED_LEVELS.len() - 1)]
+    }
+
+    pub fn faster(&mut self) {
+        self.0 = (self.0 + 1).min(SPEED_LEVELS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.0 = self.0.saturating_sub(1);
+    }
+
+    /// Picks the level at `fraction` (0 to 1) along the slider.
+    pub fn set_fraction(&mut self, fraction: f32) {
+        let last = SPEED_LEVELS.len() - 1;
+        self.0 = (fraction.clamp(0.0, 1.0) * last as f32).round() as usize;
+    }
+
+    /// Where the current level sits along the slider, 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        self.0 as f32 / (SPEED_LEVELS.len() - 1) as f32
+    }
+}
+
+/// Generations to run on the next fixed tick while paused, queued by the
+/// single-step key.
+#[derive(Resource, Default)]
+pub struct PendingGenerations(pub usize);
+
+/// The generations the board went through on the last fixed tick, the
+/// simulation's counterpart of [`Time::delta`]. Things that happen every
+/// so many generations go by this, so the speed setting only changes how
+/// fast they happen on the wall clock.
+#[derive(Resource, Default)]
+pub struct GenerationsStepped {
+    /// [`History::generation`] before the tick.
+    pub from: u64,
+    /// [`History::generation`] after it.
+    pub to: u64,
+}
+
+impl GenerationsStepped {
+    /// Whether the tick stepped the board at all.
+    pub fn any(&self) -> bool {
+        self.to > self.from
+    }
+
+    /// Whether the tick went past a multiple of `period` generations.
+    /// Going by the generation number rather than a timer means a match
+    /// replayed from a rewound generation does the same things again.
+    pub fn crossed(&self, period: u64) -> bool {
+        self.to / period > self.from / period
+    }
+}
+
+fn apply_speed(speed: Res<SimulationSpeed>, mut time: ResMut<Time<Fixed>>) {
+    if !speed.is_changed() {
+        return;
+    }
+    time.set_timestep_seconds(speed.level().seconds);
+}
+
+/// `-` and `=` slow down and speed up the simulation.
+fn change_speed(keys: Res<ButtonInput<KeyCode>>, mut speed: ResMut<SimulationSpeed>) {
+    if keys.just_pressed(KeyCode::Minus) {
+        speed.slower();
+    } else if keys.just_pressed(KeyCode::Equal) {
+        speed.faster();
+    }
+}
+
+/// `j` jumps an unbounded world of a single element far ahead.
+fn jump_generations(
+    keys: Res<ButtonInput<KeyCode>>,
+    rules: Res<Rules>,
+    mut board: ResMut<Board>,
+    mut history: ResMut<History>,
+    rng: Res<GameRng>,
+    tallies: Tallies,
+) {
+    if !keys.just_pressed(KeyCode::KeyJ) {
+        return;
+    }
+    if board.topology() != Topology::Unbounded {
+        info!("Only unbounded worlds can jump ahead");
+        return;
+    }
+    let tally = tallies.tally();
+    history.record_edits(&board, &rng, &tally);
+    if board.jump(JUMP_LOG2, &rules) {
+        history.record_jump(&board, &rng, &tally, 1 << JUMP_LOG2);
+    } else {
+        info!("Only a world of a single element can jump ahead");
+    }
+}
+
+fn reset_pending_generations(
+    mut pending: ResMut<PendingGenerations>,
+    mut stepped: ResMut<GenerationsStepped>,
+) {
+    pending.0 = 0;
+    *stepped = GenerationsStepped::default();
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SimulationSpeed>()
+        .init_resource::<PendingGenerations>()
+        .init_resource::<GenerationsStepped>()
+        .add_systems(OnEnter(Screen::Gameplay), reset_pending_generations)
+        .add_systems(
+            Update,
+            (
+                (change_speed, jump_generations)
+                    .run_if(in_state(Screen::Gameplay))
+                    .in_set(AppSystems::RecordInput)
+                    .in_set(PausableSystems),
+                apply_speed.in_set(AppSystems::Update),
+            ),
+        );
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bevy::prelude::*;
+
+use crate::{gol::cell::CellState, screens::Screen};
+
+/// How many past generations are compared against, i.e. the longest period
+/// that is noticed.
+const WINDOW: usize = 64;
+
+/// Notices when the board repeats itself, from the hashes of its most
+/// recent states.
+#[derive(Resource, Clone, Default)]
+pub struct Stability {
+    hashes: VecDeque<u64>,
+    period: Option<usize>,
+    /// Generations the board has been repeating with [`Stability::period`].
+    stable_for: u64,
+}
+
+impl Stability {
+    /// How many generations it takes the board to come back to the same
+    /// state: 1 for still lifes, more for oscillators. `None` while the
+    /// board is still changing.
+    pub fn period(&self) -> Option<usize> {
+        self.period
+    }
+
+    /// Generations the board has been stable for, zero while it changes.
+    pub fn stable_for(&self) -> u64 {
+        self.stable_for
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Adds the state the board stepped into.
+    pub fn record(&mut self, cells: &[CellState]) {
+        let mut hasher = DefaultHasher::new();
+        cells.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let period = self
+            .hashes
+            .iter()
+            .rev()
+            .position(|&seen| seen == hash)
+            .map(|back| back + 1);
+        self.stable_for = match period {
+            Some(_) if period == self.period => self.stable_for + 1,
+            Some(period) => period as u64,
+            None => 0,
+        };
+        self.period = period;
+
+        self.hashes.push_back(hash);
+        if self.hashes.len() > WINDOW {
+            self.hashes.pop_front();
+        }
+    }
+}
+
+fn reset_stability(mut stability: ResMut<Stability>) {
+    stability.clear();
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Stability>()
+        .add_systems(OnEnter(Screen::Gameplay), reset_stability);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::gol::{board::Board, cell::CellType, rule::Rules};
+
+    /// Records `board` and then `generations` steps of it.
+    fn run(board: &mut Board, generations: usize) -> Stability {
+        let rules = Rules::default();
+        let rng = &mut StdRng::seed_from_u64(0);
+        let mut stability = Stability::default();
+        stability.record(board.cells());
+        for _ in 0..generations {
+            board.step(&rules, rng);
+            stability.record(board.cells());
+        }
+        stability
+    }
+
+    fn board_with(cells: &[(usize, usize)]) -> Board {
+        let mut board = Board::new(16, 16);
+        for &(x, y) in cells {
+            board.set(x, y, CellState::Alive(CellType::TREE));
+        }
+        board
+    }
+
+    #[test]
+    fn block_has_period_one() {
+        let mut board = board_with(&[(1, 1), (2, 1), (1, 2), (2, 2)]);
+        let stability = run(&mut board, 5);
+        assert_eq!(stability.period(), Some(1));
+        assert_eq!(stability.stable_for(), 5);
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let mut board = board_with(&[(1, 2), (2, 2), (3, 2)]);
+        let stability = run(&mut board, 2);
+        assert_eq!(stability.period(), Some(2));
+        assert_eq!(stability.stable_for(), 2);
+        let stability = run(&mut board, 7);
+        assert_eq!(stability.period(), Some(2));
+        assert_eq!(stability.stable_for(), 7);
+    }
+
+    #[test]
+    fn changing_board_has_no_period() {
+        let glider = [(1, 13), (2, 12), (0, 11), (1, 11), (2, 11)];
+        let mut board = board_with(&glider);
+        let mut stability = run(&mut board, 10);
+        assert_eq!(stability.period(), None);
+        assert_eq!(stability.stable_for(), 0);
+
+        // A still board that is then changed starts over.
+        let mut board = board_with(&[(1, 1), (2, 1), (1, 2), (2, 2)]);
+        stability.clear();
+        stability.record(board.cells());
+        stability.record(board.cells());
+        assert_eq!(stability.stable_for(), 1);
+        board.set(10, 10, CellState::Alive(CellType::TREE));
+        stability.record(board.cells());
+        assert_eq!(stability.period(), None);
+        assert_eq!(stability.stable_for(), 0);
+    }
+}
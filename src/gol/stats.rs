@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use crate::{gol::cell::CellState, screens::Screen};
+
+/// How much a birth, death or conversion adds to a cell's counters.
+const BUMP: u8 = 64;
+
+/// Per-cell history gathered as the board steps, for the heat-map views.
+#[derive(Resource, Default)]
+pub struct CellStats {
+    /// Generations each cell has been alive in a row.
+    pub age: Vec<u16>,
+    /// Recent births and deaths, fading every generation.
+    pub activity: Vec<u8>,
+    /// Recent conversions from one element to another, fading every
+    /// generation.
+    pub conflict: Vec<u8>,
+}
+
+impl CellStats {
+    pub fn clear(&mut self) {
+        self.age.clear();
+        self.activity.clear();
+        self.conflict.clear();
+    }
+
+    /// Updates the counters for one generation step from `before` to `after`.
+    pub fn record(&mut self, before: &[CellState], after: &[CellState]) {
+        if self.age.len() != after.len() {
+            self.age = vec![0; after.len()];
+            self.activity = vec![0; after.len()];
+            self.conflict = vec![0; after.len()];
+        }
+        for (i, (before, after)) in before.iter().zip(after).enumerate() {
+            let activity = fade(self.activity[i]);
+            let conflict = fade(self.conflict[i]);
+            let (activity, conflict) = match (before.is_alive(), after.is_alive()) {
+                (true, true) if before.kind() != after.kind() => {
+                    (activity, conflict.saturating_add(BUMP))
+                }
+                (true, true) | (false, false) => (activity, conflict),
+                _ => (activity.saturating_add(BUMP), conflict),
+            };
+            self.activity[i] = activity;
+            self.conflict[i] = conflict;
+            self.age[i] = if after.is_alive() {
+                self.age[i].saturating_add(1)
+            } else {
+                0
+            };
+        }
+    }
+}
+
+fn fade(value: u8) -> u8 {
+    value.saturating_sub(value / 8 + 1)
+}
+
+fn reset_stats(mut stats: ResMut<CellStats>) {
+    stats.clear();
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CellStats>()
+        .add_systems(OnEnter(Screen::Gameplay), reset_stats);
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+/// What the ground under a cell is like. Terrain doesn't change as the
+/// board steps; it is set by the level and the map editor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum Terrain {
+    #[default]
+    Open,
+    /// A hole in the map. Nothing lives there, like outside the grid.
+    Void,
+    /// An indestructible obstacle. Nothing lives there either.
+    Wall,
+    /// Births need one live neighbour fewer.
+    Fertile,
+    /// Nothing is born, but cells that get there can survive.
+    Barren,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 5] = [
+        Terrain::Open,
+        Terrain::Void,
+        Terrain::Wall,
+        Terrain::Fertile,
+        Terrain::Barren,
+    ];
+
+    /// Whether cells can be alive here.
+    pub fn holds_cells(self) -> bool {
+        !matches!(self, Terrain::Void | Terrain::Wall)
+    }
+
+    /// The neighbour count a birth here is judged by, or `None` if nothing
+    /// can be born here.
+    pub fn birth_count(self, alive_neighbors: usize) -> Option<usize> {
+        match self {
+            Terrain::Open => Some(alive_neighbors),
+            Terrain::Fertile if alive_neighbors > 0 => Some(alive_neighbors + 1),
+            Terrain::Fertile | Terrain::Void | Terrain::Wall | Terrain::Barren => None,
+        }
+    }
+
+    /// The colour drawn where no cell is, if it isn't the colour of dead
+    /// cells.
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Terrain::Open => None,
+            Terrain::Void => Some(Color::BLACK),
+            Terrain::Wall => Some(Color::srgb(0.55, 0.55, 0.55)),
+            Terrain::Fertile => Some(Color::srgb(0.12, 0.2, 0.08)),
+            Terrain::Barren => Some(Color::srgb(0.25, 0.2, 0.15)),
+        }
+    }
+
+    /// The character for this terrain in level files.
+    pub fn symbol(self) -> char {
+        match self {
+            Terrain::Open => '.',
+            Terrain::Void => '_',
+            Terrain::Wall => '#',
+            Terrain::Fertile => '+',
+            Terrain::Barren => '~',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|terrain| terrain.symbol() == symbol)
+    }
+}
+
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Terrain::Open => "Open",
+            Terrain::Void => "Void",
+            Terrain::Wall => "Wall",
+            Terrain::Fertile => "Fertile",
+            Terrain::Barren => "Barren",
+        };
+        write!(f, "{}", s)
+    }
+}
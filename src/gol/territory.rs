@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+
+use crate::{
+    gol::{
+        self,
+        board::Board,
+        cell::{Owner, RegionOwner},
+        grid::{GridConfig, setup_grid},
+        region::Region,
+    },
+    screens::Screen,
+};
+
+/// Majorities offered by the settings menu, smallest first.
+pub const CONTROL_THRESHOLDS: &[f32] = &[0.5, 0.6, 0.75, 0.9];
+
+/// When an owner controls a territory, and what holding it is worth.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ControlRule {
+    /// Share of the owned live cells in a territory an owner needs more
+    /// than to control it.
+    pub threshold: f32,
+    /// Fewest cells an owner needs in a territory to control it, so a
+    /// single stray cell doesn't hold empty ground.
+    pub min_cells: u32,
+    /// Points earned for each generation a territory is held.
+    pub points: u32,
+}
+
+impl Default for ControlRule {
+    fn default() -> Self {
+        Self {
+            threshold: 0.6,
+            min_cells: 4,
+            points: 1,
+        }
+    }
+}
+
+impl ControlRule {
+    /// The next entry of [`CONTROL_THRESHOLDS`], wrapping around.
+    pub fn cycle_threshold(&mut self, step: i32) {
+        self.threshold = gol::cycle(CONTROL_THRESHOLDS, self.threshold, step);
+    }
+}
+
+/// A region of the board whose population is tracked, and who holds it.
+#[derive(Clone, Debug)]
+pub struct Territory {
+    pub name: &'static str,
+    /// The side the region starts out belonging to.
+    pub home: RegionOwner,
+    pub region: Region,
+    /// Live cells in the region by [`Owner::index`].
+    population: [u32; Owner::MAX],
+    controller: Owner,
+    held_for: u64,
+}
+
+impl Territory {
+    fn new(name: &'static str, home: RegionOwner, config: &GridConfig) -> Self {
+        Self {
+            name,
+            home,
+            region: config.region(home),
+            population: [0; Owner::MAX],
+            controller: Owner::NONE,
+            held_for: 0,
+        }
+    }
+
+    /// Live cells of `owner` in the territory.
+    pub fn population(&self, owner: Owner) -> u32 {
+        self.population.get(owner.index()).copied().unwrap_or(0)
+    }
+
+    /// Who controls the territory, if anyone.
+    pub fn controller(&self) -> Option<Owner> {
+        (self.controller != Owner::NONE).then_some(self.controller)
+    }
+
+    /// Generations the current controller has held the territory for.
+    pub fn held_for(&self) -> u64 {
+        self.held_for
+    }
+
+    fn count(&mut self, board: &Board) {
+        self.population = [0; Owner::MAX];
+        for (x, y) in self.region.cells() {
+            if board.get(x, y).is_alive()
+                && let Some(count) = self.population.get_mut(board.owner(x, y).index())
+            {
+                *count += 1;
+            }
+        }
+    }
+
+    /// The owner with a majority of the owned cells, as `rule` defines it.
+    /// Unowned cells don't count either way.
+    fn majority(&self, rule: &ControlRule) -> Owner {
+        let owned: u32 = self.population[Owner::NONE.index() + 1..].iter().sum();
+        (1..Owner::MAX as u8)
+            .map(Owner)
+            .find(|&owner| {
+                let count = self.population(owner);
+                count >= rule.min_cells && count as f32 > rule.threshold * owned as f32
+            })
+            .unwrap_or(Owner::NONE)
+    }
+}
+
+/// The regions of the match and the points earned by holding them.
+#[derive(Resource, Default)]
+pub struct Territories {
+    territories: Vec<Territory>,
+    /// By [`Owner::index`].
+    points: [u64; Owner::MAX],
+}
+
+impl Territories {
+    /// The player's region, the AI's and the ground between them, as laid
+    /// out by `config`.
+    pub fn new(config: &GridConfig) -> Self {
+        Self {
+            territories: vec![
+                Territory::new("Your base", RegionOwner::Player, config),
+                Territory::new("No man's land", RegionOwner::None, config),
+                Territory::new("AI base", RegionOwner::AI, config),
+            ],
+            points: [0; Owner::MAX],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Territory> {
+        self.territories.iter()
+    }
+
+    /// The territory starting out as `home`'s.
+    pub fn get(&self, home: RegionOwner) -> Option<&Territory> {
+        self.iter().find(|territory| territory.home == home)
+    }
+
+    /// Points `owner` has earned holding territory this match.
+    pub fn points(&self, owner: Owner) -> u64 {
+        self.points.get(owner.index()).copied().unwrap_or(0)
+    }
+
+    /// Number of territories `owner` controls.
+    pub fn held_by(&self, owner: Owner) -> usize {
+        self.iter()
+            .filter(|territory| territory.controller() == Some(owner))
+            .count()
+    }
+
+    /// Counts the population of every territory after a generation step
+    /// and awards the points for holding them.
+    pub fn record(&mut self, board: &Board, rule: &ControlRule) {
+        for territory in &mut self.territories {
+            territory.count(board);
+            let controller = territory.majority(rule);
+            if controller == territory.controller {
+                territory.held_for += 1;
+            } else {
+                territory.controller = controller;
+                territory.held_for = 0;
+            }
+            if let Some(points) = territory
+                .controller()
+                .and_then(|owner| self.points.get_mut(owner.index()))
+            {
+                *points += rule.points as u64;
+            }
+        }
+    }
+}
+
+/// What [`Territories`] has counted, without the regions, so it can be
+/// rewound with the board.
+#[derive(Clone, Default)]
+pub struct TerritoryTally {
+    /// Population, controller and generations held of each territory.
+    territories: Vec<([u32; Owner::MAX], Owner, u64)>,
+    points: [u64; Owner::MAX],
+}
+
+impl Territories {
+    pub fn tally(&self) -> TerritoryTally {
+        TerritoryTally {
+            territories: self
+                .iter()
+                .map(|territory| {
+                    (
+                        territory.population,
+                        territory.controller,
+                        territory.held_for,
+                    )
+                })
+                .collect(),
+            points: self.points,
+        }
+    }
+
+    /// Puts back what was counted when `tally` was taken; an empty tally
+    /// is the start of a match.
+    pub fn restore(&mut self, tally: &TerritoryTally) {
+        for (i, territory) in self.territories.iter_mut().enumerate() {
+            (
+                territory.population,
+                territory.controller,
+                territory.held_for,
+            ) = tally
+                .territories
+                .get(i)
+                .copied()
+                .unwrap_or(([0; Owner::MAX], Owner::NONE, 0));
+        }
+        self.points = tally.points;
+    }
+}
+
+fn reset_territories(mut territories: ResMut<Territories>, config: Res<GridConfig>) {
+    *territories = Territories::new(&config);
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ControlRule>()
+        .init_resource::<Territories>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            reset_territories.after(setup_grid),
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gol::cell::{CellState, CellType};
+
+    fn territory(player: u32, ai: u32, unowned: u32) -> Territory {
+        let mut territory = Territory::new("Test", RegionOwner::None, &GridConfig::new(10, 10));
+        territory.population[Owner::NONE.index()] = unowned;
+        territory.population[Owner::PLAYER.index()] = player;
+        territory.population[Owner::AI.index()] = ai;
+        territory
+    }
+
+    #[test]
+    fn majority_needs_more_than_the_threshold() {
+        let rule = ControlRule::default();
+        assert_eq!(territory(6, 4, 0).majority(&rule), Owner::NONE);
+        assert_eq!(territory(7, 3, 0).majority(&rule), Owner::PLAYER);
+        assert_eq!(territory(3, 7, 0).majority(&rule), Owner::AI);
+        let half = ControlRule {
+            threshold: 0.5,
+            ..rule
+        };
+        assert_eq!(territory(6, 4, 0).majority(&half), Owner::PLAYER);
+    }
+
+    #[test]
+    fn majority_needs_the_fewest_cells() {
+        let rule = ControlRule::default();
+        assert_eq!(territory(3, 0, 0).majority(&rule), Owner::NONE);
+        assert_eq!(territory(4, 0, 0).majority(&rule), Owner::PLAYER);
+    }
+
+    #[test]
+    fn majority_ignores_unowned_cells() {
+        let rule = ControlRule::default();
+        assert_eq!(territory(5, 0, 100).majority(&rule), Owner::PLAYER);
+        assert_eq!(territory(0, 0, 100).majority(&rule), Owner::NONE);
+    }
+
+    #[test]
+    fn holding_a_territory_earns_points_until_it_changes_hands() {
+        let config = GridConfig::new(10, 10);
+        let rule = ControlRule::default();
+        let mut territories = Territories::new(&config);
+        let mut board = Board::new(10, 10);
+        let base: Vec<_> = config.region(RegionOwner::Player).cells().collect();
+        let place = |board: &mut Board, cells: &[(usize, usize)], owner: Owner| {
+            for &(x, y) in cells {
+                board.set(x, y, CellState::Alive(CellType::TREE));
+                board.set_owner(x, y, owner);
+            }
+        };
+        place(&mut board, &base[..5], Owner::PLAYER);
+        territories.record(&board, &rule);
+        let held = |territories: &Territories| {
+            let territory = territories.get(RegionOwner::Player).unwrap();
+            (territory.controller(), territory.held_for())
+        };
+        assert_eq!(held(&territories), (Some(Owner::PLAYER), 0));
+        assert_eq!(territories.points(Owner::PLAYER), 1);
+
+        territories.record(&board, &rule);
+        assert_eq!(held(&territories), (Some(Owner::PLAYER), 1));
+        assert_eq!(territories.points(Owner::PLAYER), 2);
+        assert_eq!(territories.held_by(Owner::PLAYER), 1);
+
+        // Ten AI cells against the player's five take it over.
+        place(&mut board, &base[5..15], Owner::AI);
+        territories.record(&board, &rule);
+        assert_eq!(held(&territories), (Some(Owner::AI), 0));
+        assert_eq!(territories.points(Owner::AI), 1);
+        assert_eq!(territories.points(Owner::PLAYER), 2);
+        assert_eq!(territories.held_by(Owner::PLAYER), 0);
+    }
+}
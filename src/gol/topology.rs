@@ -0,0 +1,130 @@
+use std::fmt;
+
+use crate::gol;
+
+/// How the grid treats positions beyond its edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// Everything beyond the edge is dead, so gliders die at the border.
+    #[default]
+    Bounded,
+    /// Opposite edges are joined: leaving on one side re-enters on the other.
+    Torus,
+    /// Left and right edges are joined as on a torus, top and bottom are
+    /// joined with a left-right flip.
+    KleinBottle,
+    /// The edge is a solid wall that mirrors the cells just inside it.
+    Reflective,
+    /// The grid is a window onto an endless world: cells that leave it live
+    /// on out of sight. See [`SparseWorld`](crate::gol::sparse::SparseWorld).
+    Unbounded,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 5] = [
+        Topology::Bounded,
+        Topology::Torus,
+        Topology::KleinBottle,
+        Topology::Reflective,
+        Topology::Unbounded,
+    ];
+
+    /// Whether positions beyond an edge map onto the opposite edge.
+    pub fn wraps(self) -> bool {
+        matches!(self, Topology::Torus | Topology::KleinBottle)
+    }
+
+    /// Maps a possibly off-grid position onto the cell the rules should
+    /// read instead, or `None` if it counts as dead.
+    pub fn resolve(self, x: i32, y: i32, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as i32, height as i32);
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let in_bounds = (0..w).contains(&x) && (0..h).contains(&y);
+        let (x, y) = match self {
+            _ if in_bounds => (x, y),
+            // The world beyond an unbounded grid isn't on the board.
+            Topology::Bounded | Topology::Unbounded => return None,
+            Topology::Torus => (x.rem_euclid(w), y.rem_euclid(h)),
+            Topology::KleinBottle => {
+                let flips = y.div_euclid(h);
+                let x = x.rem_euclid(w);
+                let x = if flips % 2 != 0 { w - 1 - x } else { x };
+                (x, y.rem_euclid(h))
+            }
+            Topology::Reflective => (reflect(x, w)?, reflect(y, h)?),
+        };
+        Some((x as usize, y as usize))
+    }
+
+    /// Where a placed cell lands: wrapped onto the grid for wrapping
+    /// topologies, dropped when it falls off any other edge.
+    pub fn place(self, x: i32, y: i32, width: usize, height: usize) -> Option<(usize, usize)> {
+        if self.wraps() {
+            return self.resolve(x, y, width, height);
+        }
+        let in_bounds = x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height;
+        in_bounds.then_some((x as usize, y as usize))
+    }
+
+    /// The next topology in [`Topology::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+}
+
+/// Mirrors `v` back into `0..len` about the edge cells, so `-1` reads `1`
+/// and `len` reads `len - 2`, as if the wall held a copy of the cells next
+/// to it. The edge cell itself isn't repeated: a cell on the edge would
+/// otherwise count itself as a neighbour and a lone corner cell would keep
+/// itself alive. A line one cell wide has nothing to mirror, so beyond it
+/// is dead.
+fn reflect(v: i32, len: i32) -> Option<i32> {
+    if len < 2 {
+        return (0..len).contains(&v).then_some(v);
+    }
+    let period = 2 * (len - 1);
+    let v = v.rem_euclid(period);
+    Some(if v < len { v } else { period - v })
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Topology::Bounded => "Bounded",
+            Topology::Torus => "Torus",
+            Topology::KleinBottle => "Klein bottle",
+            Topology::Reflective => "Walls",
+            Topology::Unbounded => "Unbounded",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflective_edges_read_the_mirror_neighbour() {
+        let resolve = |x, y| Topology::Reflective.resolve(x, y, 4, 3);
+        assert_eq!(resolve(-1, 0), Some((1, 0)));
+        assert_eq!(resolve(4, 2), Some((2, 2)));
+        assert_eq!(resolve(-1, -1), Some((1, 1)));
+        assert_eq!(resolve(3, 3), Some((3, 1)));
+    }
+
+    #[test]
+    fn no_cell_is_its_own_reflected_neighbour() {
+        let (width, height) = (5, 4);
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                for (dx, dy) in [(-1, -1), (-1, 0), (0, -1), (1, 1), (1, 0), (0, 1)] {
+                    let resolved = Topology::Reflective.resolve(x + dx, y + dy, width, height);
+                    assert_ne!(resolved, Some((x as usize, y as usize)));
+                }
+            }
+        }
+    }
+}
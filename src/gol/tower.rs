@@ -0,0 +1,475 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    gol::{
+        board::Board,
+        cell::{CellState, CellType, Owner, RegionOwner},
+        grid::{GridConfig, game_of_life_step},
+        history::{History, Tallies},
+        interaction::{PlacementMode, find_pattern, place_pattern},
+        pattern::{Dir, Pattern, SavedPatterns},
+        player::populate_player_region,
+        region::Region,
+        rng::GameRng,
+        speed::GenerationsStepped,
+        stability::Stability,
+    },
+    screens::Screen,
+};
+
+/// Cells on a side of a tower's square footprint.
+const TOWER_SIZE: i32 = 3;
+
+/// Towers each side starts a match with.
+const TOWERS_PER_SIDE: usize = 2;
+
+/// Enemy cells in a footprint that destroy the tower, more than half of it.
+const OVERRUN_CELLS: usize = (TOWER_SIZE * TOWER_SIZE / 2 + 1) as usize;
+
+/// Generations between the shots of a tower.
+const TOWER_COOLDOWN_GENERATIONS: u64 = 15;
+
+/// Empty cells between a tower and the patterns it fires.
+const MUZZLE_GAP: i32 = 1;
+
+const PLAYER_TOWER_COLOR: Color = Color::srgb(0.3, 0.5, 1.0);
+const AI_TOWER_COLOR: Color = Color::srgb(1.0, 0.35, 0.2);
+
+/// A tower standing on the grid that fires a pattern every
+/// [`Tower::cooldown`] generations.
+#[derive(Component, Clone, Debug)]
+pub struct Tower {
+    pub owner: Owner,
+    /// The element the fired cells are.
+    pub element: CellType,
+    /// Name of the pattern in [`SavedPatterns`] it fires.
+    pub pattern: String,
+    pub heading: Dir,
+    /// Generations between shots.
+    pub cooldown: u64,
+    /// Bottom-left cell of the footprint.
+    pub cell: IVec2,
+}
+
+impl Tower {
+    /// Where `pattern`, facing the tower's heading, is placed so it starts
+    /// just outside the footprint on that side.
+    pub fn muzzle(&self, pattern: &Pattern) -> IVec2 {
+        let (width, height) = pattern
+            .cells
+            .iter()
+            .fold((0, 0), |(w, h), &(x, y)| (w.max(x + 1), h.max(y + 1)));
+        let step = heading_vector(&self.heading);
+        let along = |dir: i32, start: i32, len: i32| match dir.signum() {
+            1 => start + TOWER_SIZE + MUZZLE_GAP,
+            -1 => start - MUZZLE_GAP - len,
+            _ => start + (TOWER_SIZE - len) / 2,
+        };
+        IVec2::new(
+            along(step.x, self.cell.x, width),
+            along(step.y, self.cell.y, height),
+        )
+    }
+
+    /// Whether enemy cells fill most of the tower's footprint.
+    fn is_overrun(&self, board: &Board) -> bool {
+        let enemies = (0..TOWER_SIZE * TOWER_SIZE)
+            .filter_map(|i| {
+                let pos = self.cell + IVec2::new(i % TOWER_SIZE, i / TOWER_SIZE);
+                board.resolve(pos.x, pos.y)
+            })
+            .filter(|&(x, y)| {
+                let owner = board.owner(x, y);
+                board.get(x, y).is_alive() && owner != self.owner && owner != Owner::NONE
+            })
+            .count();
+        enemies >= OVERRUN_CELLS
+    }
+
+    /// Places the tower's pattern at its muzzle.
+    fn fire(&self, board: &mut Board, config: &GridConfig, saved: &SavedPatterns) {
+        let Some(pattern) = find_pattern(saved, &self.pattern) else {
+            return;
+        };
+        let mut pattern = pattern.clone();
+        pattern.change_heading(self.heading.clone(), config.neighbourhood);
+        let muzzle = self.muzzle(&pattern);
+        let Some((x, y)) = config.place(muzzle) else {
+            return;
+        };
+        place_pattern(
+            board,
+            config,
+            &pattern,
+            config.grid_to_world(x, y),
+            CellState::Alive(self.element),
+            self.owner,
+            PlacementMode::Union,
+        );
+    }
+}
+
+/// An overrun tower, which stays hidden and holds fire but can be put back
+/// by rewinding.
+#[derive(Component)]
+struct Overrun;
+
+/// The towers overrun so far this match, by [`Tower::cell`]. Towers don't
+/// overlap, so their cells tell them apart.
+#[derive(Resource, Clone, Default, PartialEq, Debug)]
+pub struct OverrunTowers(Vec<IVec2>);
+
+impl OverrunTowers {
+    pub fn contains(&self, tower: &Tower) -> bool {
+        self.0.contains(&tower.cell)
+    }
+}
+
+/// One step along `dir`, with y going up.
+fn heading_vector(dir: &Dir) -> IVec2 {
+    match dir {
+        Dir::N => IVec2::new(0, 1),
+        Dir::NE => IVec2::new(1, 1),
+        Dir::E => IVec2::new(1, 0),
+        Dir::SE => IVec2::new(1, -1),
+        Dir::S => IVec2::new(0, -1),
+        Dir::SW => IVec2::new(-1, -1),
+        Dir::W => IVec2::new(-1, 0),
+        Dir::NW => IVec2::new(-1, 1),
+        Dir::None | Dir::Unknown => IVec2::ZERO,
+    }
+}
+
+/// The one of the eight headings closest to `v`.
+fn heading_towards(v: Vec2) -> Dir {
+    let eighths = (v.y.atan2(v.x) / std::f32::consts::FRAC_PI_4).round();
+    let step = Vec2::from_angle(eighths * std::f32::consts::FRAC_PI_4);
+    Dir::from(step.round())
+}
+
+/// Average position of the cells of `region`, in grid coordinates.
+fn centre(region: &Region) -> Option<Vec2> {
+    let (sum, count) = region
+        .cells()
+        .fold((Vec2::ZERO, 0), |(sum, count), (x, y)| {
+            (sum + Vec2::new(x as f32, y as f32), count + 1)
+        });
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// Puts [`TOWERS_PER_SIDE`] towers in random spots of each side's region,
+/// facing the other side. Towers on a diagonal fire gliders, the others
+/// spaceships.
+fn place_towers(config: &GridConfig, board: &Board, rng: &mut GameRng) -> Vec<Tower> {
+    let sides = [
+        (Owner::PLAYER, RegionOwner::Player, RegionOwner::AI),
+        (Owner::AI, RegionOwner::AI, RegionOwner::Player),
+    ];
+    let footprint = Pattern::new_deleatable(
+        "footprint",
+        (0..TOWER_SIZE * TOWER_SIZE)
+            .map(|i| (i % TOWER_SIZE, i / TOWER_SIZE))
+            .collect(),
+    );
+    // Towers stand on ground that holds cells, not on walls or holes.
+    let blocked = Region::from_fn(config.width, config.height, |x, y| {
+        !board.terrain(x, y).holds_cells()
+    });
+    let mut towers = Vec::new();
+    for (owner, home, enemy) in sides {
+        let region = config.region(home);
+        let (Some(from), Some(to)) = (centre(&region), centre(&config.region(enemy))) else {
+            continue;
+        };
+        let heading = heading_towards(to - from);
+        let pattern = match heading {
+            Dir::NE | Dir::NW | Dir::SE | Dir::SW => "glider",
+            _ => "LWSS",
+        };
+        let element = if owner == Owner::PLAYER {
+            CellType::WATER
+        } else {
+            CellType::FIRE
+        };
+        // Towers never overlap each other.
+        let ground = region.clone().minus(&blocked);
+        let mut free = footprint.to_region_that_accepts_my_cells(&ground, config.neighbourhood);
+        for _ in 0..TOWERS_PER_SIDE {
+            let Some((x, y)) = free.random_cell(&mut rng.ai) else {
+                break;
+            };
+            let cell = IVec2::new(x as i32, y as i32);
+            free = free.minus(&Region::from_fn(config.width, config.height, |x, y| {
+                let offset = IVec2::new(x as i32, y as i32) - cell;
+                offset.abs().max_element() < TOWER_SIZE
+            }));
+            towers.push(Tower {
+                owner,
+                element,
+                pattern: pattern.to_string(),
+                heading: heading.clone(),
+                cooldown: TOWER_COOLDOWN_GENERATIONS,
+                cell,
+            });
+        }
+    }
+    towers
+}
+
+/// Spawns the towers of a new match, all of them standing.
+pub fn spawn_towers(
+    mut commands: Commands,
+    config: Res<GridConfig>,
+    board: Res<Board>,
+    mut rng: ResMut<GameRng>,
+    mut overrun: ResMut<OverrunTowers>,
+) {
+    *overrun = OverrunTowers::default();
+    for tower in place_towers(&config, &board, &mut rng) {
+        commands.spawn(tower_bundle(tower, &config));
+    }
+}
+
+/// The tower drawn as a square over its footprint with a barrel pointing
+/// the way it fires, above the board and the region overlay.
+fn tower_bundle(tower: Tower, config: &GridConfig) -> impl Bundle {
+    let color = if tower.owner == Owner::PLAYER {
+        PLAYER_TOWER_COLOR
+    } else {
+        AI_TOWER_COLOR
+    };
+    let middle = tower.cell + TOWER_SIZE / 2;
+    let position = config.grid_to_world(middle.x as usize, middle.y as usize);
+    let size = TOWER_SIZE as f32 * config.cell_size;
+    let barrel = heading_vector(&tower.heading).as_vec2() * size / 2.0;
+    (
+        Name::new("Tower"),
+        StateScoped(Screen::Gameplay),
+        Sprite::from_color(color.with_alpha(0.8), Vec2::splat(size)),
+        Transform::from_translation(position.extend(1.0)),
+        children![(
+            Sprite::from_color(color, Vec2::splat(config.cell_size)),
+            Transform::from_translation(barrel.extend(0.1)),
+        )],
+        tower,
+    )
+}
+
+/// Fires the standing towers whose cooldown ran out in the generations
+/// just stepped, and records the shots in the [`History`] so they rewind
+/// like any other edit. Towers hold fire while the board repeats itself,
+/// as the AI holds its spawns.
+fn fire_towers(
+    towers: Query<&Tower, Without<Overrun>>,
+    mut board: ResMut<Board>,
+    stepped: Res<GenerationsStepped>,
+    stability: Res<Stability>,
+    config: Res<GridConfig>,
+    saved: Res<SavedPatterns>,
+    mut history: ResMut<History>,
+    rng: Res<GameRng>,
+    tallies: Tallies,
+) {
+    if stability.period().is_some() {
+        return;
+    }
+    let mut fired = false;
+    for tower in &towers {
+        if stepped.crossed(tower.cooldown) {
+            tower.fire(&mut board, &config, &saved);
+            fired = true;
+        }
+    }
+    if fired {
+        history.record_edits(&board, &rng, &tallies.tally());
+    }
+}
+
+/// Marks towers whose footprint the generations just stepped filled with
+/// enemy cells as overrun, in the [`History`] too so rewinding past it
+/// puts them back.
+fn overrun_towers(
+    mut commands: Commands,
+    towers: Query<(Entity, &Tower), Without<Overrun>>,
+    board: Res<Board>,
+    stepped: Res<GenerationsStepped>,
+    mut overrun: ResMut<OverrunTowers>,
+    mut history: ResMut<History>,
+) {
+    if !stepped.any() {
+        return;
+    }
+    let mut any = false;
+    for (entity, tower) in &towers {
+        if tower.is_overrun(&board) {
+            info!("A tower at {} was overrun", tower.cell);
+            overrun.0.push(tower.cell);
+            commands
+                .entity(entity)
+                .insert((Overrun, Visibility::Hidden));
+            any = true;
+        }
+    }
+    if any {
+        history.amend_tally(|tally| tally.overrun_towers = overrun.clone());
+    }
+}
+
+/// Hides the towers [`OverrunTowers`] lists and shows the rest, after a
+/// rewind changed it.
+fn show_standing_towers(
+    mut commands: Commands,
+    towers: Query<(Entity, &Tower)>,
+    overrun: Res<OverrunTowers>,
+) {
+    for (entity, tower) in &towers {
+        if overrun.contains(tower) {
+            commands
+                .entity(entity)
+                .insert((Overrun, Visibility::Hidden));
+        } else {
+            commands
+                .entity(entity)
+                .remove::<Overrun>()
+                .insert(Visibility::Inherited);
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<OverrunTowers>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            spawn_towers.after(populate_player_region),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                overrun_towers.after(game_of_life_step),
+                fire_towers.after(overrun_towers),
+            )
+                .in_set(AppSystems::Update)
+                .in_set(PausableSystems),
+        )
+        .add_systems(
+            Update,
+            show_standing_towers
+                .run_if(in_state(Screen::Gameplay).and(resource_changed::<OverrunTowers>))
+                .in_set(AppSystems::Update),
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gol::terrain::Terrain;
+
+    fn tower(owner: Owner, heading: Dir) -> Tower {
+        Tower {
+            owner,
+            element: CellType::WATER,
+            pattern: "glider".to_string(),
+            heading,
+            cooldown: TOWER_COOLDOWN_GENERATIONS,
+            cell: IVec2::new(10, 10),
+        }
+    }
+
+    #[test]
+    fn muzzle_is_just_outside_the_footprint_on_the_heading() {
+        let square = Pattern::new_deleatable("square", vec![(0, 0), (2, 2)]);
+        let (near, far) = (10 - MUZZLE_GAP - 3, 10 + TOWER_SIZE + MUZZLE_GAP);
+        for (heading, muzzle) in [
+            (Dir::N, IVec2::new(10, far)),
+            (Dir::NE, IVec2::new(far, far)),
+            (Dir::E, IVec2::new(far, 10)),
+            (Dir::SE, IVec2::new(far, near)),
+            (Dir::S, IVec2::new(10, near)),
+            (Dir::SW, IVec2::new(near, near)),
+            (Dir::W, IVec2::new(near, 10)),
+            (Dir::NW, IVec2::new(near, far)),
+            (Dir::None, IVec2::new(10, 10)),
+        ] {
+            assert_eq!(
+                tower(Owner::PLAYER, heading.clone()).muzzle(&square),
+                muzzle,
+                "{heading:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn muzzle_leaves_the_gap_before_a_pattern_fired_backwards() {
+        // Five wide and one tall, so it's centred across the footprint.
+        let bar = Pattern::new_deleatable("bar", (0..5).map(|x| (x, 0)).collect());
+        let tower = tower(Owner::PLAYER, Dir::W);
+        let muzzle = tower.muzzle(&bar);
+        assert_eq!(muzzle.x + 5 + MUZZLE_GAP, tower.cell.x);
+        assert_eq!(muzzle.y, tower.cell.y + 1);
+        let tower = Tower {
+            heading: Dir::S,
+            ..tower
+        };
+        let muzzle = tower.muzzle(&bar);
+        assert_eq!(muzzle.y + 1 + MUZZLE_GAP, tower.cell.y);
+        assert_eq!(muzzle.x, tower.cell.x - 1);
+    }
+
+    #[test]
+    fn towers_stand_apart_on_open_ground_of_their_side() {
+        let config = GridConfig::new(30, 30);
+        let mut board = Board::new(30, 30);
+        for (x, y) in Region::from_fn(30, 30, |x, _| x % 4 == 0).cells() {
+            board.set_terrain(x, y, Terrain::Wall);
+        }
+        for seed in 0..20 {
+            let towers = place_towers(&config, &board, &mut GameRng::fixed(seed));
+            for (owner, home) in [
+                (Owner::PLAYER, RegionOwner::Player),
+                (Owner::AI, RegionOwner::AI),
+            ] {
+                let region = config.region(home);
+                let side: Vec<_> = towers.iter().filter(|t| t.owner == owner).collect();
+                assert_eq!(side.len(), TOWERS_PER_SIDE);
+                for tower in &side {
+                    for i in 0..TOWER_SIZE * TOWER_SIZE {
+                        let pos = tower.cell + IVec2::new(i % TOWER_SIZE, i / TOWER_SIZE);
+                        let (x, y) = (pos.x as usize, pos.y as usize);
+                        assert!(region.contains(x, y), "{pos} outside {home:?}");
+                        assert!(board.terrain(x, y).holds_cells(), "{pos} on a wall");
+                    }
+                }
+            }
+            for (i, a) in towers.iter().enumerate() {
+                for b in &towers[i + 1..] {
+                    assert!((a.cell - b.cell).abs().max_element() >= TOWER_SIZE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn towers_are_overrun_by_most_of_their_footprint() {
+        assert_eq!(OVERRUN_CELLS, 5);
+        let mut board = Board::new(20, 20);
+        let tower = tower(Owner::PLAYER, Dir::N);
+        let mut footprint = (0..TOWER_SIZE * TOWER_SIZE).map(|i| {
+            let pos = tower.cell + IVec2::new(i % TOWER_SIZE, i / TOWER_SIZE);
+            (pos.x as usize, pos.y as usize)
+        });
+        let mut fill = |board: &mut Board, owner: Owner, count: usize| {
+            for (x, y) in footprint.by_ref().take(count) {
+                board.set(x, y, CellState::Alive(CellType::FIRE));
+                board.set_owner(x, y, owner);
+            }
+        };
+        // The tower's own and unowned cells don't count against it.
+        fill(&mut board, Owner::PLAYER, 2);
+        fill(&mut board, Owner::NONE, 2);
+        fill(&mut board, Owner::AI, OVERRUN_CELLS - 1);
+        assert!(!tower.is_overrun(&board));
+        fill(&mut board, Owner::AI, 1);
+        assert!(tower.is_overrun(&board));
+    }
+}
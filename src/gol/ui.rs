@@ -1,13 +1,26 @@
-use super::cell::{Cell, CellState};
+use super::board::Board;
+use super::cell::CellState;
+use super::elements::Elements;
+use super::grid::GridConfig;
+use super::history::History;
+use super::interaction::PlacementMode;
+use super::level::{TerrainBrush, save_custom_level};
 use super::pattern::{SavedPatterns, SelectedPattern};
+use super::render::{RenderMode, ShowRegions};
+use super::rule::Rules;
+use super::speed::SimulationSpeed;
 use super::state::Playing;
 use crate::gol::cell::CellType;
 use crate::gol::patterns_io::{save_pattern, save_patterns};
 use crate::screens::Screen;
 use crate::theme::widget;
+use crate::{AppSystems, PausableSystems};
 use bevy::ecs::hierarchy::ChildSpawnerCommands;
 use bevy::ecs::spawn::SpawnRelatedBundle;
-use bevy::{prelude::*, ui::Val::*};
+use bevy::{
+    prelude::*,
+    ui::{RelativeCursorPosition, Val::*},
+};
 
 #[derive(Component)]
 pub struct GameMenuRoot;
@@ -15,6 +28,33 @@ pub struct GameMenuRoot;
 #[derive(Component)]
 struct ClearButton;
 
+#[derive(Component)]
+struct PlayPauseButton;
+
+#[derive(Component)]
+struct PlacementModeButton;
+
+#[derive(Component)]
+struct RenderModeButton;
+
+#[derive(Component)]
+struct ShowRegionsButton;
+
+#[derive(Component)]
+struct TerrainBrushButton;
+
+#[derive(Component)]
+struct SaveMapButton;
+
+#[derive(Component)]
+struct SpeedSlider;
+
+#[derive(Component)]
+struct SpeedFill;
+
+#[derive(Component)]
+struct SpeedLabel;
+
 #[derive(Component)]
 struct PatternButtons;
 
@@ -46,11 +86,80 @@ const PLAY_COLOR: Color = Color::srgb(0.5, 0.0, 0.0); // dark red
 const PAUSE_COLOR: Color = Color::srgb(0.0, 0.5, 0.0); // dark green
 const PATTERN_COLOR: Color = Color::srgb(0.5, 0.5, 0.5); // dark grey
 const PATTERN_SELECTED_COLOR: Color = Color::srgb(0.8, 0.8, 0.5); // yellowish grey
+const SPEED_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
 
-fn spawn_ui(mut commands: Commands, saved: Res<SavedPatterns>, selected: Res<SelectedPattern>) {
+fn spawn_ui(
+    mut commands: Commands,
+    saved: Res<SavedPatterns>,
+    selected: Res<SelectedPattern>,
+    playing: Res<Playing>,
+    speed: Res<SimulationSpeed>,
+    mode: Res<PlacementMode>,
+    render_mode: Res<RenderMode>,
+    show_regions: Res<ShowRegions>,
+    brush: Res<TerrainBrush>,
+    rules: Res<Rules>,
+) {
+    let (play_label, play_color) = play_button_look(playing.0);
     commands
         .spawn((widget::ui_root_right("GameMenu"), GameMenuRoot))
         .with_children(|root| {
+            root.spawn((
+                Button,
+                PlayPauseButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("PlayPauseButton"),
+                BackgroundColor(play_color),
+            ))
+            .with_child((
+                Text::new(play_label),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            root.spawn((
+                Text::new(format!("Speed: {}", speed.level().label)),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::BLACK),
+                TextLayout::default(),
+                SpeedLabel,
+            ));
+            root.spawn((
+                Button,
+                SpeedSlider,
+                RelativeCursorPosition::default(),
+                Name::new("SpeedSlider"),
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(12.0),
+                    margin: UiRect::horizontal(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(SPEED_COLOR),
+            ))
+            .with_child((
+                SpeedFill,
+                Node {
+                    width: Val::Percent(speed.fraction() * 100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(PATTERN_SELECTED_COLOR),
+                Pickable::IGNORE,
+            ));
             root.spawn((
                 Button,
                 ClearButton,
@@ -77,7 +186,122 @@ fn spawn_ui(mut commands: Commands, saved: Res<SavedPatterns>, selected: Res<Sel
                     TextLayout::default(),
                 ));
             });
-            spawn_pattern_buttons_in_parent(root, &saved, &selected);
+            root.spawn((
+                Button,
+                PlacementModeButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::horizontal(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("PlacementModeButton"),
+                BackgroundColor(PATTERN_COLOR),
+            ))
+            .with_child((
+                Text::new(format!("Place: {}", *mode)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            root.spawn((
+                Button,
+                RenderModeButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("RenderModeButton"),
+                BackgroundColor(PATTERN_COLOR),
+            ))
+            .with_child((
+                Text::new(format!("View: {}", *render_mode)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            root.spawn((
+                Button,
+                ShowRegionsButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("ShowRegionsButton"),
+                BackgroundColor(PATTERN_COLOR),
+            ))
+            .with_child((
+                Text::new(show_regions_label(*show_regions)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            root.spawn((
+                Button,
+                TerrainBrushButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("TerrainBrushButton"),
+                BackgroundColor(PATTERN_COLOR),
+            ))
+            .with_child((
+                Text::new(format!("Paint: {}", *brush)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            root.spawn((
+                Button,
+                SaveMapButton,
+                Node {
+                    width: Val::Px(150.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                Name::new("SaveMapButton"),
+                BackgroundColor(PATTERN_COLOR),
+            ))
+            .with_child((
+                Text::new("Save Map"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::default(),
+            ));
+            spawn_pattern_buttons_in_parent(root, &saved, &selected, &rules.elements);
         });
 }
 
@@ -91,6 +315,7 @@ fn refresh_pattern_buttons(
     mut commands: Commands,
     saved: Res<SavedPatterns>,
     selected: Res<SelectedPattern>,
+    rules: Res<Rules>,
     pattern_list_query: Query<(Entity, Option<&Children>), With<PatternButtons>>,
 ) {
     if !saved.is_changed() {
@@ -109,7 +334,7 @@ fn refresh_pattern_buttons(
         }
         // Now spawn new pattern buttons as children
         commands.entity(parent).with_children(|patterns_root| {
-            spawn_pattern_buttons(patterns_root, &saved, &selected);
+            spawn_pattern_buttons(patterns_root, &saved, &selected, &rules.elements);
         });
     }
 }
@@ -118,12 +343,13 @@ fn spawn_pattern_buttons_in_parent(
     parent: &mut ChildSpawnerCommands,
     saved: &SavedPatterns,
     selected: &SelectedPattern,
+    elements: &Elements,
 ) {
     parent
         .spawn(build_pattern_buttons_bundle())
         .with_child(build_pattern_button_bundle())
         .with_children(|parent| {
-            spawn_pattern_buttons(parent, saved, selected);
+            spawn_pattern_buttons(parent, saved, selected, elements);
         });
 }
 
@@ -131,12 +357,13 @@ fn spawn_pattern_buttons_in_root(
     commands: &mut Commands,
     saved: &SavedPatterns,
     selected: &SelectedPattern,
+    elements: &Elements,
 ) {
     commands
         .spawn(build_pattern_buttons_bundle())
         .with_child(build_pattern_button_bundle())
         .with_children(|parent| {
-            spawn_pattern_buttons(parent, saved, selected);
+            spawn_pattern_buttons(parent, saved, selected, elements);
         });
 }
 
@@ -174,6 +401,7 @@ fn spawn_pattern_buttons(
     patterns_root: &mut ChildSpawnerCommands,
     saved: &SavedPatterns,
     selected: &SelectedPattern,
+    elements: &Elements,
 ) {
     patterns_root.spawn((
         Text::new("Patterns:"),
@@ -191,7 +419,7 @@ fn spawn_pattern_buttons(
 
     for (name, pattern) in patterns {
         let (preview_node, preview_color, preview_children) =
-            pattern_preview(&to_state(&pattern.cells));
+            pattern_preview(&to_state(&pattern.cells), elements);
         let color = if name == &selected.0 {
             PATTERN_SELECTED_COLOR // Highlight the selected pattern
         } else {
@@ -289,7 +517,7 @@ fn to_state(pattern: &Vec<(i32, i32)>) -> Vec<Vec<CellState>> {
 
     for &(x, y) in pattern {
         if x >= 0 && y >= 0 {
-            grid[y as usize][x as usize] = CellState::Alive(CellType::Water);
+            grid[y as usize][x as usize] = CellState::Alive(CellType::WATER);
         }
     }
 
@@ -298,6 +526,7 @@ fn to_state(pattern: &Vec<(i32, i32)>) -> Vec<Vec<CellState>> {
 
 fn pattern_preview(
     pattern: &[Vec<CellState>],
+    elements: &Elements,
 ) -> (
     Node,
     BackgroundColor,
@@ -311,7 +540,7 @@ fn pattern_preview(
     let mut children = Vec::new();
     for (y, row) in pattern.iter().enumerate() {
         for (x, state) in row.iter().enumerate() {
-            let color = state.color();
+            let color = elements.color(state.kind());
             children.push((
                 Node {
                     width: Val::Px(cell_size),
@@ -346,12 +575,12 @@ fn handle_pattern_buttons(
     mut selected: ResMut<SelectedPattern>,
 ) {
     for (interaction, name) in &mut interaction_query {
-        if *interaction == Interaction::Pressed {
-            if let Some(pattern_name) = name.as_str().strip_prefix("PatternButton:") {
-                selected.0 = pattern_name.to_string();
-                // output to console the selected pattern
-                info!("Selected pattern: {}", selected.0);
-            }
+        if *interaction == Interaction::Pressed
+            && let Some(pattern_name) = name.as_str().strip_prefix("PatternButton:")
+        {
+            selected.0 = pattern_name.to_string();
+            // output to console the selected pattern
+            info!("Selected pattern: {}", selected.0);
         }
     }
 }
@@ -385,12 +614,12 @@ fn handle_delete_pattern_buttons(
     >,
 ) {
     for (interaction, name) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            if let Some(pattern_name) = name.as_str().strip_prefix("DeletePatternButton:") {
-                saved.0.remove(pattern_name);
-                save_patterns(saved);
-                break;
-            }
+        if *interaction == Interaction::Pressed
+            && let Some(pattern_name) = name.as_str().strip_prefix("DeletePatternButton:")
+        {
+            saved.0.remove(pattern_name);
+            save_patterns(saved);
+            break;
         }
     }
 }
@@ -403,59 +632,275 @@ fn handle_save_pattern_buttons(
     >,
 ) {
     for (interaction, name) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            if let Some(pattern_name) = name.as_str().strip_prefix("SavePatternButton:") {
-                save_pattern(pattern_name.to_string(), saved);
-                break;
-            }
+        if *interaction == Interaction::Pressed
+            && let Some(pattern_name) = name.as_str().strip_prefix("SavePatternButton:")
+        {
+            save_pattern(pattern_name.to_string(), saved);
+            break;
         }
     }
 }
 
+/// Label and colour of the play/pause button: it shows what pressing it does.
+fn play_button_look(playing: bool) -> (&'static str, Color) {
+    if playing {
+        ("Pause", PAUSE_COLOR)
+    } else {
+        ("Play", PLAY_COLOR)
+    }
+}
+
 fn handle_play_button(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &Children, &Name),
-        (Changed<Interaction>, With<Button>, With<Name>),
-    >,
-    mut text_query: Query<&mut Text>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<PlayPauseButton>)>,
     mut playing: ResMut<Playing>,
+    mut history: ResMut<History>,
 ) {
-    for (interaction, mut bg_color, children, name) in &mut interaction_query {
-        if *interaction == Interaction::Pressed && name.as_str() == "PlayPauseButton" {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            if !playing.0 {
+                // Resume from the generation on screen.
+                history.resume();
+            }
             playing.0 = !playing.0;
-            let label = if playing.0 { "Pause" } else { "Play" };
-            for child in children.iter() {
-                if let Ok(mut text) = text_query.get_mut(child) {
-                    text.0 = label.to_string();
-                }
+        }
+    }
+}
+
+/// Keeps the play/pause button in sync with [`Playing`], which the keyboard
+/// and the timeline change too.
+fn update_play_button(
+    playing: Res<Playing>,
+    mut button_query: Query<(&mut BackgroundColor, &Children), With<PlayPauseButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !playing.is_changed() {
+        return;
+    }
+    let (label, color) = play_button_look(playing.0);
+    for (mut bg_color, children) in &mut button_query {
+        *bg_color = BackgroundColor(color);
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = label.to_string();
             }
-            *bg_color = if playing.0 {
-                BackgroundColor(PAUSE_COLOR)
-            } else {
-                BackgroundColor(PLAY_COLOR)
-            };
         }
     }
 }
 
+/// Clicking or dragging on the speed slider picks the nearest speed.
+fn handle_speed_slider(
+    slider: Query<(&Interaction, &RelativeCursorPosition), With<SpeedSlider>>,
+    mut speed: ResMut<SimulationSpeed>,
+) {
+    for (interaction, cursor) in &slider {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(position) = cursor.normalized {
+            let mut picked = *speed;
+            picked.set_fraction(position.x);
+            speed.set_if_neq(picked);
+        }
+    }
+}
+
+fn update_speed_slider(
+    speed: Res<SimulationSpeed>,
+    mut fill: Query<&mut Node, With<SpeedFill>>,
+    mut label: Query<&mut Text, With<SpeedLabel>>,
+) {
+    if !speed.is_changed() {
+        return;
+    }
+    for mut node in &mut fill {
+        node.width = Val::Percent(speed.fraction() * 100.0);
+    }
+    for mut text in &mut label {
+        text.0 = format!("Speed: {}", speed.level().label);
+    }
+}
+
 fn handle_clear_button(
     query: Query<(&Interaction, &Name), (Changed<Interaction>, With<ClearButton>)>,
-    mut cell_query: Query<(&mut Sprite, &mut Cell)>,
+    mut board: ResMut<Board>,
 ) {
     for (interaction, name) in query.iter() {
         if *interaction == Interaction::Pressed && name.as_str() == "ClearButton" {
-            for (mut sprite, mut cell) in &mut cell_query {
-                cell.state = CellState::Dead;
-                sprite.color = cell.state.color();
+            board.clear();
+        }
+    }
+}
+
+/// Clicking the placement mode button cycles through [`PlacementMode::ALL`].
+fn handle_placement_mode_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<PlacementModeButton>)>,
+    mut mode: ResMut<PlacementMode>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            *mode = mode.cycle(1);
+        }
+    }
+}
+
+fn update_placement_mode_button(
+    mode: Res<PlacementMode>,
+    button_query: Query<&Children, With<PlacementModeButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+    for children in &button_query {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = format!("Place: {}", *mode);
             }
         }
     }
 }
 
+/// Clicking the view button cycles through [`RenderMode::ALL`].
+fn handle_render_mode_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<RenderModeButton>)>,
+    mut mode: ResMut<RenderMode>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            *mode = mode.cycle(1);
+        }
+    }
+}
+
+fn update_render_mode_button(
+    mode: Res<RenderMode>,
+    button_query: Query<&Children, With<RenderModeButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+    for children in &button_query {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = format!("View: {}", *mode);
+            }
+        }
+    }
+}
+
+fn show_regions_label(show: ShowRegions) -> &'static str {
+    if show.0 {
+        "Regions: On"
+    } else {
+        "Regions: Off"
+    }
+}
+
+fn handle_show_regions_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ShowRegionsButton>)>,
+    mut show: ResMut<ShowRegions>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            show.0 = !show.0;
+        }
+    }
+}
+
+fn update_show_regions_button(
+    show: Res<ShowRegions>,
+    button_query: Query<&Children, With<ShowRegionsButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !show.is_changed() {
+        return;
+    }
+    for children in &button_query {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = show_regions_label(*show).to_string();
+            }
+        }
+    }
+}
+
+/// Clicking the paint button turns the map editor on and cycles through
+/// the terrain it paints.
+fn handle_terrain_brush_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<TerrainBrushButton>)>,
+    mut brush: ResMut<TerrainBrush>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            *brush = brush.cycle(1);
+        }
+    }
+}
+
+fn update_terrain_brush_button(
+    brush: Res<TerrainBrush>,
+    button_query: Query<&Children, With<TerrainBrushButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !brush.is_changed() {
+        return;
+    }
+    for children in &button_query {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = format!("Paint: {}", *brush);
+            }
+        }
+    }
+}
+
+fn handle_save_map_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<SaveMapButton>)>,
+    board: Res<Board>,
+    mut config: ResMut<GridConfig>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            save_custom_level(&board, &mut config);
+        }
+    }
+}
+
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Gameplay), spawn_ui)
         .add_systems(OnExit(Screen::Gameplay), cleanup_game_menu)
-        .add_systems(Update, (handle_play_button, handle_clear_button))
+        .add_systems(Update, handle_clear_button)
+        .add_systems(
+            Update,
+            (
+                (handle_play_button, handle_speed_slider).in_set(AppSystems::RecordInput),
+                (update_play_button, update_speed_slider).in_set(AppSystems::Update),
+            )
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        )
+        .add_systems(
+            Update,
+            (handle_placement_mode_button, update_placement_mode_button),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_render_mode_button,
+                update_render_mode_button,
+                handle_show_regions_button,
+                update_show_regions_button,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_terrain_brush_button,
+                update_terrain_brush_button,
+                handle_save_map_button,
+            ),
+        )
         .add_systems(Update, (handle_pattern_buttons, refresh_pattern_buttons))
         .add_systems(Update, (update_pattern_button_highlights,))
         .add_systems(
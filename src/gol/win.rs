@@ -0,0 +1,586 @@
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+
+use crate::gol::{
+    self,
+    board::Board,
+    cell::{Owner, RegionOwner},
+    stability::Stability,
+    territory::Territories,
+};
+
+/// How a match can end. A match ends with the first of its conditions that
+/// is met.
+///
+/// In level files each condition is a `win:` line in the format
+/// [`WinCondition`]'s `Display` writes, e.g. `win: hold middle 300`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinCondition {
+    /// A side with no live cells left loses, if it had some this match and
+    /// the other side still does.
+    Eliminate,
+    /// The player wins by still having cells after this many generations.
+    Survive(u64),
+    /// Whoever holds the territory starting as `home`'s for this many
+    /// generations in a row wins.
+    Hold { home: RegionOwner, generations: u64 },
+    /// Whoever's score reaches this first wins.
+    ReachScore(i32),
+    /// The player loses when their score drops below this.
+    ScoreBelow(i32),
+    /// Once the board has been stable for this many generations the match
+    /// is decided by the tiebreak.
+    Stagnation {
+        generations: u64,
+        tiebreak: Tiebreak,
+    },
+}
+
+/// How a match that has stopped changing is decided.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tiebreak {
+    Draw,
+    /// Whoever earned more territory points wins, then whoever owns more
+    /// live cells.
+    Territory,
+}
+
+/// How a match ended: who won, if anyone, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verdict {
+    pub winner: Option<Owner>,
+    pub reason: String,
+}
+
+impl Verdict {
+    /// What the game-over screen calls the result.
+    pub fn message(&self) -> &'static str {
+        match self.winner {
+            Some(Owner::PLAYER) => "You win!",
+            Some(_) => "You lose.",
+            None => "Draw.",
+        }
+    }
+}
+
+/// What the conditions are judged on.
+pub struct MatchView<'a> {
+    pub board: &'a Board,
+    pub territories: &'a Territories,
+    pub stability: &'a Stability,
+    pub generation: u64,
+    pub player_score: i32,
+    pub ai_score: i32,
+    pub fielded: Fielded,
+}
+
+/// Which owners have had live cells this match, so a side that never
+/// fielded any isn't eliminated.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fielded([bool; Owner::MAX]);
+
+impl Fielded {
+    /// Notes the owners with live cells on `board`.
+    pub fn record(&mut self, board: &Board) {
+        for owner in [Owner::PLAYER, Owner::AI] {
+            if board.count_owned(owner) > 0 {
+                self.0[owner.index()] = true;
+            }
+        }
+    }
+
+    pub fn has(&self, owner: Owner) -> bool {
+        self.0.get(owner.index()).copied().unwrap_or(false)
+    }
+}
+
+impl WinCondition {
+    /// The verdict if this condition ends the match now.
+    pub fn check(&self, view: &MatchView) -> Option<Verdict> {
+        let verdict = |winner: Option<Owner>, reason: String| Some(Verdict { winner, reason });
+        match *self {
+            WinCondition::Eliminate => {
+                let player = view.board.count_owned(Owner::PLAYER);
+                let ai = view.board.count_owned(Owner::AI);
+                if ai == 0 && player > 0 && view.fielded.has(Owner::AI) {
+                    verdict(Some(Owner::PLAYER), "The AI has no cells left.".to_string())
+                } else if player == 0 && ai > 0 && view.fielded.has(Owner::PLAYER) {
+                    verdict(Some(Owner::AI), "You have no cells left.".to_string())
+                } else {
+                    None
+                }
+            }
+            WinCondition::Survive(generations) => (view.generation >= generations
+                && view.board.count_owned(Owner::PLAYER) > 0)
+                .then(|| Verdict {
+                    winner: Some(Owner::PLAYER),
+                    reason: format!("You survived {generations} generations."),
+                }),
+            WinCondition::Hold { home, generations } => {
+                let territory = view.territories.get(home)?;
+                let holder = territory.controller()?;
+                if territory.held_for() < generations {
+                    return None;
+                }
+                let who = if holder == Owner::PLAYER {
+                    "You"
+                } else {
+                    "The AI"
+                };
+                verdict(
+                    Some(holder),
+                    format!(
+                        "{who} held {} for {generations} generations.",
+                        territory.name
+                    ),
+                )
+            }
+            WinCondition::ReachScore(score) => {
+                if view.player_score >= score {
+                    verdict(
+                        Some(Owner::PLAYER),
+                        format!("You reached a score of {score}."),
+                    )
+                } else if view.ai_score >= score {
+                    verdict(
+                        Some(Owner::AI),
+                        format!("The AI reached a score of {score}."),
+                    )
+                } else {
+                    None
+                }
+            }
+            WinCondition::ScoreBelow(score) => (view.player_score < score).then(|| Verdict {
+                winner: Some(Owner::AI),
+                reason: format!("Your score fell below {score}."),
+            }),
+            WinCondition::Stagnation {
+                generations,
+                tiebreak,
+            } => {
+                let period = view.stability.period()?;
+                if generations == 0 || view.stability.stable_for() < generations {
+                    return None;
+                }
+                let player = (
+                    view.territories.points(Owner::PLAYER),
+                    view.board.count_owned(Owner::PLAYER),
+                );
+                let ai = (
+                    view.territories.points(Owner::AI),
+                    view.board.count_owned(Owner::AI),
+                );
+                let (winner, outcome) = match tiebreak {
+                    Tiebreak::Territory if player > ai => (Some(Owner::PLAYER), "won"),
+                    Tiebreak::Territory if player < ai => (Some(Owner::AI), "lost"),
+                    _ => (None, "drew"),
+                };
+                let on = match tiebreak {
+                    Tiebreak::Territory => " on territory",
+                    Tiebreak::Draw => "",
+                };
+                verdict(
+                    winner,
+                    format!(
+                        "The board was stable (period {period}) for {generations} generations; you {outcome}{on}."
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Checks `conditions` in order and returns the first verdict.
+pub fn judge(conditions: &[WinCondition], view: &MatchView) -> Option<Verdict> {
+    conditions
+        .iter()
+        .find_map(|condition| condition.check(view))
+}
+
+fn home_name(home: RegionOwner) -> &'static str {
+    match home {
+        RegionOwner::Player => "you",
+        RegionOwner::None => "middle",
+        RegionOwner::AI => "ai",
+    }
+}
+
+impl fmt::Display for WinCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WinCondition::Eliminate => write!(f, "eliminate"),
+            WinCondition::Survive(generations) => write!(f, "survive {generations}"),
+            WinCondition::Hold { home, generations } => {
+                write!(f, "hold {} {generations}", home_name(*home))
+            }
+            WinCondition::ReachScore(score) => write!(f, "score {score}"),
+            WinCondition::ScoreBelow(score) => write!(f, "below {score}"),
+            WinCondition::Stagnation {
+                generations,
+                tiebreak,
+            } => {
+                let tiebreak = match tiebreak {
+                    Tiebreak::Draw => "draw",
+                    Tiebreak::Territory => "territory",
+                };
+                write!(f, "stagnation {generations} {tiebreak}")
+            }
+        }
+    }
+}
+
+/// A `win:` line that isn't a [`WinCondition`].
+#[derive(Debug)]
+pub struct WinConditionParseError(pub String);
+
+impl fmt::Display for WinConditionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a win condition", self.0)
+    }
+}
+
+impl std::error::Error for WinConditionParseError {}
+
+impl FromStr for WinCondition {
+    type Err = WinConditionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || WinConditionParseError(s.trim().to_string());
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let number = |word: &str| word.parse().map_err(|_| err());
+        let condition = match words.as_slice() {
+            ["eliminate"] => WinCondition::Eliminate,
+            ["survive", generations] => WinCondition::Survive(number(generations)?),
+            ["hold", home, generations] => WinCondition::Hold {
+                home: match *home {
+                    "you" => RegionOwner::Player,
+                    "middle" => RegionOwner::None,
+                    "ai" => RegionOwner::AI,
+                    _ => return Err(err()),
+                },
+                generations: number(generations)?,
+            },
+            ["score", score] => WinCondition::ReachScore(score.parse().map_err(|_| err())?),
+            ["below", score] => WinCondition::ScoreBelow(score.parse().map_err(|_| err())?),
+            ["stagnation", generations, tiebreak] => WinCondition::Stagnation {
+                generations: number(generations)?,
+                tiebreak: match *tiebreak {
+                    "draw" => Tiebreak::Draw,
+                    "territory" => Tiebreak::Territory,
+                    _ => return Err(err()),
+                },
+            },
+            _ => return Err(err()),
+        };
+        Ok(condition)
+    }
+}
+
+/// Sets of win conditions to pick from in the settings menu. A level with
+/// its own `win:` lines plays by those instead.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    /// Wipe out the AI without wasting too much water.
+    #[default]
+    Classic,
+    /// Keep some plants alive for long enough.
+    Survival,
+    /// Hold the middle of the board.
+    KingOfTheHill,
+    /// Be first to a score.
+    ScoreRace,
+}
+
+const STAGNATION: WinCondition = WinCondition::Stagnation {
+    generations: 500,
+    tiebreak: Tiebreak::Territory,
+};
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Classic,
+        GameMode::Survival,
+        GameMode::KingOfTheHill,
+        GameMode::ScoreRace,
+    ];
+
+    /// The next mode in [`GameMode::ALL`], wrapping around.
+    pub fn cycle(self, step: i32) -> Self {
+        gol::cycle(&Self::ALL, self, step)
+    }
+
+    pub fn conditions(self) -> Vec<WinCondition> {
+        match self {
+            GameMode::Classic => vec![
+                WinCondition::Eliminate,
+                WinCondition::ScoreBelow(-100),
+                STAGNATION,
+            ],
+            GameMode::Survival => vec![
+                WinCondition::Eliminate,
+                WinCondition::Survive(2000),
+                STAGNATION,
+            ],
+            GameMode::KingOfTheHill => vec![
+                WinCondition::Hold {
+                    home: RegionOwner::None,
+                    generations: 300,
+                },
+                WinCondition::Eliminate,
+                STAGNATION,
+            ],
+            GameMode::ScoreRace => vec![
+                WinCondition::ReachScore(300),
+                WinCondition::ScoreBelow(-100),
+                STAGNATION,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            GameMode::Classic => "Classic",
+            GameMode::Survival => "Survival",
+            GameMode::KingOfTheHill => "King of the Hill",
+            GameMode::ScoreRace => "Score Race",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gol::{
+        cell::{CellState, CellType},
+        grid::GridConfig,
+        territory::ControlRule,
+    };
+
+    const CONDITIONS: [WinCondition; 8] = [
+        WinCondition::Eliminate,
+        WinCondition::Survive(2000),
+        WinCondition::Hold {
+            home: RegionOwner::Player,
+            generations: 1,
+        },
+        WinCondition::Hold {
+            home: RegionOwner::None,
+            generations: 300,
+        },
+        WinCondition::Hold {
+            home: RegionOwner::AI,
+            generations: 0,
+        },
+        WinCondition::ReachScore(300),
+        WinCondition::ScoreBelow(-100),
+        WinCondition::Stagnation {
+            generations: 500,
+            tiebreak: Tiebreak::Draw,
+        },
+    ];
+
+    #[test]
+    fn conditions_parse_back_from_how_they_display() {
+        for condition in CONDITIONS.into_iter().chain([STAGNATION]) {
+            let parsed: WinCondition = condition.to_string().parse().unwrap();
+            assert_eq!(parsed, condition);
+        }
+        assert_eq!(
+            "  hold   middle 300 ".parse::<WinCondition>().unwrap(),
+            CONDITIONS[3]
+        );
+    }
+
+    #[test]
+    fn malformed_conditions_are_rejected() {
+        for line in [
+            "",
+            "win",
+            "eliminate now",
+            "survive",
+            "survive forever",
+            "survive -1",
+            "hold base 300",
+            "hold middle",
+            "score 1.5",
+            "below",
+            "stagnation 500",
+            "stagnation 500 coin",
+        ] {
+            assert!(line.parse::<WinCondition>().is_err(), "{line:?} parsed");
+        }
+    }
+
+    /// A 10 by 10 board with banded regions.
+    fn config() -> GridConfig {
+        GridConfig::new(10, 10)
+    }
+
+    /// Puts `count` live cells of `owner` in the region starting as `home`'s.
+    fn place(board: &mut Board, home: RegionOwner, owner: Owner, count: usize) {
+        for (x, y) in config().region(home).cells().take(count) {
+            board.set(x, y, CellState::Alive(CellType::TREE));
+            board.set_owner(x, y, owner);
+        }
+    }
+
+    fn view<'a>(
+        board: &'a Board,
+        territories: &'a Territories,
+        stability: &'a Stability,
+    ) -> MatchView<'a> {
+        let mut fielded = Fielded::default();
+        fielded.record(board);
+        MatchView {
+            board,
+            territories,
+            stability,
+            generation: 0,
+            player_score: 0,
+            ai_score: 0,
+            fielded,
+        }
+    }
+
+    fn winner(condition: WinCondition, view: &MatchView) -> Option<Option<Owner>> {
+        judge(&[condition], view).map(|verdict| verdict.winner)
+    }
+
+    #[test]
+    fn eliminate_needs_the_loser_to_have_fielded_cells() {
+        let mut board = Board::new(10, 10);
+        let territories = Territories::new(&config());
+        let stability = Stability::default();
+        place(&mut board, RegionOwner::Player, Owner::PLAYER, 3);
+        let view = view(&board, &territories, &stability);
+        assert_eq!(winner(WinCondition::Eliminate, &view), None);
+
+        let mut fielded = view.fielded;
+        fielded.0[Owner::AI.index()] = true;
+        let view = MatchView { fielded, ..view };
+        assert_eq!(
+            winner(WinCondition::Eliminate, &view),
+            Some(Some(Owner::PLAYER))
+        );
+    }
+
+    #[test]
+    fn survive_needs_live_cells_at_the_generation() {
+        let mut board = Board::new(10, 10);
+        let territories = Territories::new(&config());
+        let stability = Stability::default();
+        let condition = WinCondition::Survive(100);
+        let generation = |generation| MatchView {
+            generation,
+            ..view(&board, &territories, &stability)
+        };
+        assert_eq!(winner(condition, &generation(100)), None);
+
+        place(&mut board, RegionOwner::Player, Owner::PLAYER, 1);
+        let generation = |generation| MatchView {
+            generation,
+            ..view(&board, &territories, &stability)
+        };
+        assert_eq!(winner(condition, &generation(99)), None);
+        assert_eq!(
+            winner(condition, &generation(100)),
+            Some(Some(Owner::PLAYER))
+        );
+    }
+
+    #[test]
+    fn hold_needs_the_territory_for_long_enough() {
+        let mut board = Board::new(10, 10);
+        let mut territories = Territories::new(&config());
+        let stability = Stability::default();
+        place(&mut board, RegionOwner::None, Owner::AI, 5);
+        for _ in 0..4 {
+            territories.record(&board, &ControlRule::default());
+        }
+        let view = view(&board, &territories, &stability);
+        let hold = |home, generations| WinCondition::Hold { home, generations };
+        assert_eq!(
+            winner(hold(RegionOwner::None, 3), &view),
+            Some(Some(Owner::AI))
+        );
+        assert_eq!(winner(hold(RegionOwner::None, 4), &view), None);
+        assert_eq!(winner(hold(RegionOwner::Player, 0), &view), None);
+    }
+
+    #[test]
+    fn scores_are_compared_against_the_target() {
+        let board = Board::new(10, 10);
+        let territories = Territories::new(&config());
+        let stability = Stability::default();
+        let scores = |player_score, ai_score| MatchView {
+            player_score,
+            ai_score,
+            ..view(&board, &territories, &stability)
+        };
+        let reach = WinCondition::ReachScore(300);
+        assert_eq!(winner(reach, &scores(299, 299)), None);
+        assert_eq!(winner(reach, &scores(300, 0)), Some(Some(Owner::PLAYER)));
+        assert_eq!(winner(reach, &scores(0, 300)), Some(Some(Owner::AI)));
+        let below = WinCondition::ScoreBelow(-100);
+        assert_eq!(winner(below, &scores(-100, 0)), None);
+        assert_eq!(winner(below, &scores(-101, 0)), Some(Some(Owner::AI)));
+    }
+
+    #[test]
+    fn stagnation_waits_for_the_board_to_settle_then_breaks_the_tie() {
+        let mut board = Board::new(10, 10);
+        let mut territories = Territories::new(&config());
+        let mut stability = Stability::default();
+        place(&mut board, RegionOwner::Player, Owner::PLAYER, 5);
+        territories.record(&board, &ControlRule::default());
+        // The first state has nothing to repeat, the rest repeat it.
+        for _ in 0..10 {
+            stability.record(board.cells());
+        }
+        let stagnation = |generations, tiebreak| WinCondition::Stagnation {
+            generations,
+            tiebreak,
+        };
+        let view = view(&board, &territories, &stability);
+        assert_eq!(winner(stagnation(10, Tiebreak::Territory), &view), None);
+        assert_eq!(
+            winner(stagnation(9, Tiebreak::Territory), &view),
+            Some(Some(Owner::PLAYER))
+        );
+        assert_eq!(winner(stagnation(9, Tiebreak::Draw), &view), Some(None));
+        assert_eq!(winner(stagnation(0, Tiebreak::Draw), &view), None);
+
+        let changing = Stability::default();
+        let view = MatchView {
+            stability: &changing,
+            ..view
+        };
+        assert_eq!(winner(stagnation(1, Tiebreak::Draw), &view), None);
+    }
+
+    #[test]
+    fn judge_takes_the_first_condition_met() {
+        let board = Board::new(10, 10);
+        let territories = Territories::new(&config());
+        let stability = Stability::default();
+        let view = MatchView {
+            player_score: 300,
+            ..view(&board, &territories, &stability)
+        };
+        let verdict = judge(
+            &[
+                WinCondition::Survive(1),
+                WinCondition::ScoreBelow(400),
+                WinCondition::ReachScore(300),
+            ],
+            &view,
+        )
+        .unwrap();
+        assert_eq!(verdict.winner, Some(Owner::AI));
+        assert_eq!(verdict.reason, "Your score fell below 400.");
+    }
+}
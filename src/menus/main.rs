@@ -29,6 +29,7 @@ fn spawn_main_menu(mut commands: Commands) {
     ));
 }
 
+#[allow(unused_mut, unused_variables)]
 fn enter_loading_or_gameplay_screen(
     _: Trigger<Pointer<Click>>,
     resource_handles: Res<ResourceHandles>,
@@ -4,7 +4,19 @@
 
 use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+use crate::{
+    gol::{
+        elements::{ElementAssets, Elements},
+        grid::GridConfig,
+        level::{Level, LevelAssets},
+        rule::{BattleRule, Rules},
+        territory::ControlRule,
+        win::GameMode,
+    },
+    menus::Menu,
+    screens::Screen,
+    theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
@@ -16,21 +28,82 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<GlobalVolumeLabel>();
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_global_volume_label,
+            update_cycle_labels,
+            keep_battle_rule_working,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
-fn spawn_settings_menu(mut commands: Commands) {
-    commands.spawn((
-        widget::ui_root("Settings Menu"),
-        GlobalZIndex(2),
-        StateScoped(Menu::Settings),
-        children![
-            widget::header("Settings"),
-            settings_grid(),
-            widget::button("Back", go_back_on_click),
-        ],
-    ));
+fn spawn_settings_menu(mut commands: Commands, screen: Res<State<Screen>>) {
+    let settings_grid = commands.spawn(settings_grid()).id();
+    // The grid size is fixed for the duration of a match.
+    if screen.get() == &Screen::Title {
+        commands.entity(settings_grid).with_children(|grid| {
+            grid.spawn(setting_label("Grid Size"));
+            grid.spawn(cycle_widget(
+                "Grid Size",
+                |config: &GridConfig, _| format!("{} x {}", config.width, config.height),
+                |config, step, _| config.cycle_preset(step),
+            ));
+            grid.spawn(setting_label("Edges"));
+            grid.spawn(cycle_widget(
+                "Edges",
+                |config: &GridConfig, _| config.topology.to_string(),
+                |config, step, _| config.topology = config.topology.cycle(step),
+            ));
+            grid.spawn(setting_label("Neighbours"));
+            grid.spawn(cycle_widget(
+                "Neighbours",
+                |config: &GridConfig, _| config.neighbourhood.to_string(),
+                |config, step, _| config.neighbourhood = config.neighbourhood.cycle(step),
+            ));
+            grid.spawn(setting_label("Element Set"));
+            grid.spawn(cycle_widget(
+                "Element Set",
+                |rules: &Rules, _| {
+                    let names: Vec<&str> = rules.elements.iter().map(|e| e.name.as_str()).collect();
+                    format!("{} ({})", rules.elements.name, names.join(", "))
+                },
+                cycle_element_set,
+            ));
+            grid.spawn(setting_label("Map"));
+            grid.spawn(cycle_widget(
+                "Map",
+                |config: &GridConfig, _| config.level.name.clone(),
+                cycle_level,
+            ));
+            grid.spawn(setting_label("Regions"));
+            grid.spawn(cycle_widget(
+                "Regions",
+                |config: &GridConfig, _| config.regions.to_string(),
+                |config, step, _| config.regions = config.regions.cycle(step),
+            ));
+            grid.spawn(setting_label("Control"));
+            grid.spawn(cycle_widget(
+                "Control",
+                |control: &ControlRule, _| format!("{:.0}% to hold", control.threshold * 100.0),
+                |control, step, _| control.cycle_threshold(step),
+            ));
+            grid.spawn(setting_label("Goal"));
+            grid.spawn(cycle_widget(
+                "Goal",
+                game_mode_text,
+                |mode: &mut GameMode, step, _| *mode = mode.cycle(step),
+            ));
+        });
+    }
+    commands
+        .spawn((
+            widget::ui_root("Settings Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::Settings),
+            children![widget::header("Settings")],
+        ))
+        .add_child(settings_grid)
+        .with_child(widget::button("Back", go_back_on_click));
 }
 
 fn settings_grid() -> impl Bundle {
@@ -44,18 +117,121 @@ fn settings_grid() -> impl Bundle {
             ..default()
         },
         children![
+            setting_label("Master Volume"),
+            global_volume_widget(),
+            setting_label("Rule"),
+            cycle_widget(
+                "Rule",
+                |rules: &Rules, _| match rules.rule.preset_name() {
+                    Some(name) => format!("{name} ({})", rules.rule),
+                    None => rules.rule.to_string(),
+                },
+                |rules, step, _| rules.rule = rules.rule.cycle_preset(step),
+            ),
+            setting_label("Elements"),
+            cycle_widget(
+                "Elements",
+                |rules: &Rules, _| if rules.per_element {
+                    "Own rule each".to_string()
+                } else {
+                    "Shared rule".to_string()
+                },
+                |rules, _, _| rules.per_element = !rules.per_element,
+            ),
+            setting_label("Battle"),
+            cycle_widget(
+                "Battle",
+                |rules: &Rules, _| rules.battle.to_string(),
+                |rules, step, _| rules.battle = rules.battle.cycle(step),
+            ),
+            setting_label("Decay"),
+            cycle_widget(
+                "Decay",
+                |rules: &Rules, _| if rules.decay {
+                    "Dead cells linger".to_string()
+                } else {
+                    "Dead cells vanish".to_string()
+                },
+                |rules, _, _| rules.decay = !rules.decay,
+            ),
+        ],
+    )
+}
+
+/// The name of a setting, in the left column of the grid.
+fn setting_label(text: &'static str) -> impl Bundle {
+    (
+        widget::label(text),
+        Node {
+            justify_self: JustifySelf::End,
+            ..default()
+        },
+    )
+}
+
+/// A "< value >" setting kept in resource `T`. `get` writes the value
+/// shown and `cycle` steps it by -1 or 1; both may look at the rest of the
+/// world, e.g. for assets.
+fn cycle_widget<T: Resource>(
+    name: &'static str,
+    get: fn(&T, &World) -> String,
+    cycle: fn(&mut T, i32, &World),
+) -> impl Bundle {
+    let step = move |step: i32| {
+        move |_: Trigger<Pointer<Click>>, mut commands: Commands| {
+            commands.queue(move |world: &mut World| {
+                world.resource_scope(|world, mut value: Mut<T>| cycle(&mut value, step, world));
+            });
+        }
+    };
+    (
+        Name::new(format!("{name} Widget")),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("<", step(-1)),
             (
-                widget::label("Master Volume"),
+                Name::new(format!("Current {name}")),
                 Node {
-                    justify_self: JustifySelf::End,
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
                     ..default()
-                }
+                },
+                children![(
+                    widget::label(""),
+                    CycleLabel(Box::new(move |world: &World| {
+                        world
+                            .get_resource::<T>()
+                            .map_or_else(String::new, |value| get(value, world))
+                    })),
+                )],
             ),
-            global_volume_widget(),
+            widget::button_small(">", step(1)),
         ],
     )
 }
 
+/// The text of a [`cycle_widget`], read from the world.
+#[derive(Component)]
+struct CycleLabel(Box<dyn Fn(&World) -> String + Send + Sync>);
+
+fn update_cycle_labels(world: &mut World) {
+    let mut labels = world.query::<(Entity, &CycleLabel)>();
+    let texts: Vec<(Entity, String)> = labels
+        .iter(world)
+        .map(|(entity, label)| (entity, (label.0)(world)))
+        .collect();
+    for (entity, text) in texts {
+        if let Some(mut current) = world.get_mut::<Text>(entity)
+            && current.0 != text
+        {
+            current.0 = text;
+        }
+    }
+}
+
 fn global_volume_widget() -> impl Bundle {
     (
         Name::new("Global Volume Widget"),
@@ -104,6 +280,38 @@ fn update_global_volume_label(
     label.0 = format!("{percent:3.0}%");
 }
 
+fn cycle_element_set(rules: &mut Rules, step: i32, world: &World) {
+    let sets = world.get_resource::<ElementAssets>();
+    let assets = world.resource::<Assets<Elements>>();
+    if let Some(elements) = sets.and_then(|sets| sets.cycle(&rules.elements.name, step, assets)) {
+        rules.elements = elements;
+    }
+}
+
+fn cycle_level(config: &mut GridConfig, step: i32, world: &World) {
+    if let Some(levels) = world.get_resource::<LevelAssets>() {
+        config.level = levels.cycle(&config.level.name, step, world.resource::<Assets<Level>>());
+    }
+}
+
+/// Falls back to [`BattleRule::TopTwo`] when the chosen edges can't fight
+/// by the chosen battle rule.
+fn keep_battle_rule_working(config: Res<GridConfig>, mut rules: ResMut<Rules>) {
+    if !rules.battle.works_with(config.topology) {
+        rules.battle = BattleRule::TopTwo;
+    }
+}
+
+/// Levels with their own win conditions override the mode.
+fn game_mode_text(mode: &GameMode, world: &World) -> String {
+    let level = &world.resource::<GridConfig>().level;
+    if level.win.is_empty() {
+        mode.to_string()
+    } else {
+        format!("Set by {}", level.name)
+    }
+}
+
 fn go_back_on_click(
     _: Trigger<Pointer<Click>>,
     screen: Res<State<Screen>>,